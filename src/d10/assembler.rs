@@ -0,0 +1,325 @@
+use std::{collections::{BTreeMap, HashMap}, io::{Read, BufReader, BufRead}};
+
+use super::{parse, CrtConfig};
+use super::cpu::{opcode, Cpu, Instruction, OpCode, Operand, Register};
+
+type Step = (usize, i32, Option<i32>);
+
+struct Assembler {
+    // Program is executed while being assembled so `draw` knows the current cycle and register
+    cpu: Cpu,
+    config: CrtConfig,
+    symbols: HashMap<String, i32>,
+    // Instructions waiting for their symbols: line number, opcode and operand expressions
+    pending: Vec<(usize, &'static OpCode, Vec<String>)>,
+}
+
+fn is_identifier(name: &str) -> bool {
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {},
+        _ => return false,
+    }
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Assembler {
+    fn new(config: CrtConfig) -> Self {
+        Self {
+            cpu: Cpu::new(Vec::new()),
+            config,
+            symbols: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self, opcode: &'static OpCode, args: Vec<Operand>) {
+        self.cpu.push(Instruction { opcode, args });
+        self.cpu.run(&mut []);
+    }
+
+    fn next_cycle(&self) -> i32 {
+        let pending = self.pending.iter().map(|(_, opcode, _)| opcode.cycles).sum::<u32>();
+        (self.cpu.cycle() + pending) as i32 + 1
+    }
+
+    // Emits pending instructions now that their symbols are known
+    fn flush(&mut self) -> Result<(), String> {
+        for (number, opcode, exprs) in std::mem::take(&mut self.pending) {
+            let args = opcode.operands.iter()
+                .zip(exprs.iter())
+                .map(|(kind, expr)| kind.parse(expr, |expr| self.value(expr)))
+                .collect::<Result<Vec<Operand>, String>>();
+            match args {
+                Ok(args) => self.emit(opcode, args),
+                Err(e) => return Err(format!("Line {number}: {e}")),
+            }
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, value: i32) -> Result<(), String> {
+        if !is_identifier(name) {
+            return Err(format!("Invalid symbol name '{name}'"));
+        }
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(format!("Symbol {name} defined twice"));
+        }
+        Ok(())
+    }
+
+    fn term(&self, term: &str) -> Result<i32, String> {
+        if let Ok(num) = term.parse::<i32>() {
+            return Ok(num);
+        }
+        match self.symbols.get(term) {
+            Some(value) => Ok(*value),
+            None => Err(format!("Unknown symbol {term}")),
+        }
+    }
+
+    // Sum of numbers and symbols, e.g. `WIDTH - 3` or `-offset`
+    fn value(&self, expr: &str) -> Result<i32, String> {
+        let mut total = 0;
+        let mut sign = 1;
+        let mut term = String::new();
+        for c in expr.chars().filter(|c| !c.is_whitespace()) {
+            match c {
+                '+' | '-' if term.is_empty() => {
+                    if c == '-' {
+                        sign = -sign;
+                    }
+                },
+                '+' | '-' => {
+                    total += sign * self.term(&term)?;
+                    term.clear();
+                    sign = if c == '-' { -1 } else { 1 };
+                },
+                _ => term.push(c),
+            }
+        }
+        if term.is_empty() {
+            return Err(format!("Incomplete expression '{expr}'"));
+        }
+        Ok(total + sign * self.term(&term)?)
+    }
+
+    fn line(&mut self, number: usize, line: &str) -> Result<(), String> {
+        let mut line = match line.find(';') {
+            Some(pos) => &line[..pos],
+            None => line,
+        }.trim();
+
+        if let Some(pos) = line.find(':') {
+            let label = &line[..pos];
+            let cycle = self.next_cycle();
+            if let Err(e) = self.define(label.trim(), cycle) {
+                return Err(format!("Line {number}: {e}"));
+            }
+            line = line[pos + 1..].trim();
+        }
+
+        let (name, rest) = match line.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (line, ""),
+        };
+        if name == "draw" {
+            // Draw has to know the register value, so everything before it gets executed
+            self.flush()?;
+        }
+        match self.statement(number, name, rest) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Line {number}: {e}")),
+        }
+    }
+
+    fn statement(&mut self, number: usize, name: &str, rest: &str) -> Result<(), String> {
+        match name {
+            "" => Ok(()),
+            "const" => match rest.split_once('=') {
+                Some((symbol, expr)) => {
+                    let value = self.value(expr)?;
+                    self.define(symbol.trim(), value)
+                },
+                None => Err(String::from("Expected 'const NAME = value'")),
+            },
+            "draw" => self.draw(rest.trim_matches('"')),
+            name => {
+                let opcode = opcode(name)?;
+                let exprs = match rest {
+                    "" => Vec::new(),
+                    rest => rest.split(',').map(|expr| expr.trim().to_string()).collect::<Vec<String>>(),
+                };
+                if exprs.len() != opcode.operands.len() {
+                    return Err(format!("Invalid {name} param count, expected {}", opcode.operands.len()));
+                }
+                self.pending.push((number, opcode, exprs));
+                Ok(())
+            },
+        }
+    }
+
+    // Plans the shortest noop/addx sequence that draws given pixels starting at the current cycle
+    fn draw(&mut self, pattern: &str) -> Result<(), String> {
+        let pixels = pattern.chars().map(|c| match c {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            _ => Err(format!("Invalid pixel '{c}', expected '#' or '.'")),
+        }).collect::<Result<Vec<bool>, String>>()?;
+        if pixels.is_empty() {
+            return Err(String::from("Nothing to draw"));
+        }
+
+        let noop = opcode("noop")?;
+        let addx = opcode("addx")?;
+        let start = self.next_cycle();
+        let matches = |cycles: std::ops::Range<usize>, x: i32| {
+            cycles.end <= pixels.len() && cycles.into_iter().all(|i| self.config.pixel_lit(start + i as i32, x) == pixels[i])
+        };
+        // Any register value outside of the screen behaves the same
+        let targets = -self.config.sprite_width..=self.config.width + self.config.sprite_width;
+
+        // For every drawn pixel count and register value: (instruction count, previous register value, addx param)
+        let mut best: Vec<BTreeMap<i32, Step>> = vec![BTreeMap::new(); pixels.len() + 1];
+        best[0].insert(self.cpu.register(Register::X), (0, 0, None));
+        for i in 0..pixels.len() {
+            let states = best[i].iter().map(|(x, (count, _, _))| (*x, *count)).collect::<Vec<(i32, usize)>>();
+            for (x, count) in states {
+                let mut relax = |pos: usize, next: i32, add: Option<i32>| {
+                    let entry = best[pos].entry(next).or_insert((usize::MAX, x, add));
+                    if count + 1 < entry.0 {
+                        *entry = (count + 1, x, add);
+                    }
+                };
+                if matches(i..i + noop.cycles as usize, x) {
+                    relax(i + noop.cycles as usize, x, None);
+                }
+                if matches(i..i + addx.cycles as usize, x) {
+                    for next in targets.clone() {
+                        relax(i + addx.cycles as usize, next, Some(next - x));
+                    }
+                }
+            }
+        }
+
+        let mut pos = pixels.len();
+        let mut x = match best[pos].iter().min_by_key(|(_, (count, _, _))| *count) {
+            Some((x, _)) => *x,
+            None => return Err(format!("Unable to draw '{pattern}' from cycle {start}")),
+        };
+        let mut planned = Vec::new();
+        while pos > 0 {
+            let (_, prev, add) = best[pos][&x];
+            planned.push(add);
+            pos -= match add {
+                Some(_) => addx.cycles as usize,
+                None => noop.cycles as usize,
+            };
+            x = prev;
+        }
+        for add in planned.into_iter().rev() {
+            match add {
+                Some(num) => self.emit(addx, vec![Operand::Number(num)]),
+                None => self.emit(noop, Vec::new()),
+            }
+        }
+        Ok(())
+    }
+}
+
+// Turns assembly with labels, constants, comments and `draw` into plain program for `parse`.
+// Instruction operands can use symbols defined later in the program, except before a `draw`,
+// which executes everything above it to plan its instructions. Constants can only use symbols
+// defined above them.
+pub fn assemble<R: Read>(input: BufReader<R>) -> Result<String, String> {
+    let mut assembler = Assembler::new(CrtConfig::default());
+    for (number, line) in input.lines().enumerate() {
+        match line {
+            Ok(ln) => assembler.line(number + 1, &ln)?,
+            Err(e) => return Err(format!("Unable to read line: {e}")),
+        }
+    }
+    assembler.flush()?;
+    Ok(assembler.cpu.program().iter().map(|i| format!("{i}\n")).collect())
+}
+
+pub fn disassemble<R: Read>(input: BufReader<R>) -> Result<String, String> {
+    let mut cpu = Cpu::new(parse(input).collect::<Result<Vec<Instruction>, String>>()?);
+    let mut output = String::new();
+    while let Some(instruction) = cpu.current_instruction() {
+        let text = instruction.to_string();
+        let start = cpu.cycle() + 1;
+        cpu.step(&mut []);
+        let cycles = match cpu.cycle() {
+            end if end == start => format!("cycle {start}"),
+            end => format!("cycles {start}-{end}"),
+        };
+        output.push_str(&format!("{:<12}; {}, x = {}\n", text, cycles, cpu.register(Register::X)));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::Crt;
+    use crate::test_util::str_to_buf_reader;
+
+    fn screen(program: &str) -> String {
+        let mut crt = Crt::new(CrtConfig::default());
+        let mut cpu = Cpu::new(parse(str_to_buf_reader(&program.to_string())).collect::<Result<Vec<Instruction>, String>>().unwrap());
+        cpu.run(&mut [&mut crt]);
+        crt.screen()
+    }
+
+    #[test]
+    fn test_d10_assemble() {
+        let input = String::from("; constants and labels
+const STEP = 3
+start: addx STEP + 1  ; x = 5
+noop
+after: addx after - start - 8
+");
+        assert_eq!(assemble(str_to_buf_reader(&input)).unwrap(), "addx 4\nnoop\naddx -5\n");
+
+        let row1 = "####....#..#..###...####..##....####....";
+        let row2 = "..###...##..##....####....#######.......";
+        let input = format!("draw {row1}\ndraw \"{row2}\"");
+        let program = assemble(str_to_buf_reader(&input)).unwrap();
+        assert_eq!(screen(&program), format!("{row1}\n{row2}\n"));
+        assert_eq!(row1.len() as i32, CrtConfig::default().width);
+
+        // Register only changes every other cycle
+        let err = assemble(str_to_buf_reader(&String::from("draw #.."))).unwrap_err();
+        assert_eq!(err, "Line 1: Unable to draw '#..' from cycle 1");
+
+        let err = assemble(str_to_buf_reader(&String::from("noop\naddx missing"))).unwrap_err();
+        assert_eq!(err, "Line 2: Unknown symbol missing");
+        assert!(assemble(str_to_buf_reader(&String::from("a: noop\na: noop"))).is_err());
+
+        // Labels and constants can be used before they are defined
+        let input = String::from("addx end - start
+addx WIDTH
+start: noop
+end: noop
+const WIDTH = 40
+");
+        assert_eq!(assemble(str_to_buf_reader(&input)).unwrap(), "addx 1\naddx 40\nnoop\nnoop\n");
+        let input = String::from("addx end\ndraw ##\nend: noop");
+        assert_eq!(assemble(str_to_buf_reader(&input)).unwrap_err(), "Line 1: Unknown symbol end");
+        let input = String::from("addx 2\ndraw ###\nset y, after\nafter: addx 1");
+        assert_eq!(assemble(str_to_buf_reader(&input)).unwrap(), "addx 2\nnoop\naddx -6\nset y, 7\naddx 1\n");
+        assert!(assemble(str_to_buf_reader(&String::from("draw #x#"))).is_err());
+    }
+
+    #[test]
+    fn test_d10_disassemble() {
+        let input = String::from("noop\naddx 3\naddx -5\n");
+        let output = disassemble(str_to_buf_reader(&input)).unwrap();
+        assert_eq!(output, "noop        ; cycle 1, x = 1
+addx 3      ; cycles 2-3, x = 4
+addx -5     ; cycles 4-5, x = -1
+");
+        // Annotated program is valid assembly
+        assert_eq!(assemble(str_to_buf_reader(&output)).unwrap(), input);
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt;
+
+// Name and initial value of every register, adding a register is a matter of adding an entry here
+const REGISTER_TABLE: &[(&str, i32)] = &[
+    ("x", 1),
+    ("y", 0),
+];
+
+pub const REGISTER_COUNT: usize = REGISTER_TABLE.len();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register(usize);
+
+impl Register {
+    // Sprite position used by the CRT and the signal strength
+    pub const X: Register = Register(0);
+
+    pub fn all() -> impl Iterator<Item = Register> {
+        (0..REGISTER_COUNT).map(Register)
+    }
+
+    pub fn name(self) -> &'static str {
+        REGISTER_TABLE[self.0].0
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match REGISTER_TABLE.iter().position(|(n, _)| *n == name) {
+            Some(index) => Ok(Register(index)),
+            None => Err(format!("Unknown register {name}")),
+        }
+    }
+}
+
+type Registers = [i32; REGISTER_COUNT];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    Number,
+    Register,
+    // Number or register
+    Value,
+}
+
+impl OperandKind {
+    // Numbers are parsed by the given function, so the assembler can evaluate expressions
+    pub fn parse(self, text: &str, number: impl Fn(&str) -> Result<i32, String>) -> Result<Operand, String> {
+        match self {
+            OperandKind::Number => Ok(Operand::Number(number(text)?)),
+            OperandKind::Register => Ok(Operand::Register(Register::parse(text)?)),
+            OperandKind::Value => match Register::parse(text) {
+                Ok(register) => Ok(Operand::Register(register)),
+                Err(_) => Ok(Operand::Number(number(text)?)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Number(i32),
+    Register(Register),
+}
+
+impl Operand {
+    fn value(self, registers: &Registers) -> i32 {
+        match self {
+            Operand::Number(num) => num,
+            Operand::Register(register) => registers[register.0],
+        }
+    }
+
+    // Parsing makes sure there is a register wherever the opcode expects one
+    fn index(self) -> usize {
+        match self {
+            Operand::Register(register) => register.0,
+            Operand::Number(num) => unreachable!("{num} is not a register"),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Number(num) => write!(f, "{num}"),
+            Operand::Register(register) => write!(f, "{}", register.name()),
+        }
+    }
+}
+
+pub struct OpCode {
+    pub name: &'static str,
+    pub cycles: u32,
+    pub operands: &'static [OperandKind],
+    pub execute: fn(&mut Registers, &[Operand]),
+}
+
+// Adding an instruction is a matter of adding an entry here
+pub const INSTRUCTIONS: &[OpCode] = &[
+    OpCode {
+        name: "noop",
+        cycles: 1,
+        operands: &[],
+        execute: |_, _| {},
+    },
+    OpCode {
+        name: "addx",
+        cycles: 2,
+        operands: &[OperandKind::Number],
+        execute: |registers, args| registers[Register::X.0] += args[0].value(registers),
+    },
+    OpCode {
+        name: "add",
+        cycles: 2,
+        operands: &[OperandKind::Register, OperandKind::Value],
+        execute: |registers, args| registers[args[0].index()] += args[1].value(registers),
+    },
+    OpCode {
+        name: "set",
+        cycles: 1,
+        operands: &[OperandKind::Register, OperandKind::Value],
+        execute: |registers, args| registers[args[0].index()] = args[1].value(registers),
+    },
+];
+
+pub fn opcode(name: &str) -> Result<&'static OpCode, String> {
+    match INSTRUCTIONS.iter().find(|op| op.name == name) {
+        Some(op) => Ok(op),
+        None => Err(format!("Invalid command {name}")),
+    }
+}
+
+pub struct Instruction {
+    pub opcode: &'static OpCode,
+    pub args: Vec<Operand>,
+}
+
+impl Instruction {
+    // Operands are separated by commas, e.g. `add y, x`
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut parts = line.split(|c: char| c.is_whitespace() || c == ',').filter(|part| !part.is_empty());
+        let name = match parts.next() {
+            Some(name) => name,
+            None => return Err(format!("Invalid line {line}")),
+        };
+        let opcode = opcode(name)?;
+        let parts = parts.collect::<Vec<&str>>();
+        if parts.len() != opcode.operands.len() {
+            return Err(format!("Invalid {name} param count, expected {}", opcode.operands.len()));
+        }
+        let number = |arg: &str| match arg.parse::<i32>() {
+            Ok(num) => Ok(num),
+            Err(_) => Err(format!("{name} param must be a number")),
+        };
+        let args = opcode.operands.iter()
+            .zip(parts)
+            .map(|(kind, arg)| kind.parse(arg, number))
+            .collect::<Result<Vec<Operand>, String>>()?;
+        Ok(Self { opcode, args })
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.opcode.name)?;
+        for (index, arg) in self.args.iter().enumerate() {
+            match index {
+                0 => write!(f, " {arg}")?,
+                _ => write!(f, ", {arg}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Observers get called during every cycle, before the instruction finishing
+// in that cycle changes the registers
+pub trait Observer {
+    fn during_cycle(&mut self, cycle: u32, cpu: &Cpu);
+}
+
+pub struct Cpu {
+    program: Vec<Instruction>,
+    registers: Registers,
+    pc: usize,
+    cycle: u32,
+    elapsed: u32, // cycles already spent on the current instruction
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            program,
+            registers: std::array::from_fn(|index| REGISTER_TABLE[index].1),
+            pc: 0,
+            cycle: 0,
+            elapsed: 0,
+        }
+    }
+
+    pub fn push(&mut self, instruction: Instruction) {
+        self.program.push(instruction);
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    pub fn register(&self, register: Register) -> i32 {
+        self.registers[register.0]
+    }
+
+    pub fn cycle(&self) -> u32 {
+        self.cycle
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn current_instruction(&self) -> Option<&Instruction> {
+        self.program.get(self.pc)
+    }
+
+    // True when no cycle of the current instruction has been executed yet
+    pub fn at_instruction_start(&self) -> bool {
+        self.elapsed == 0
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    // Run a single cycle, returns false when there is nothing left to execute
+    pub fn tick(&mut self, observers: &mut [&mut dyn Observer]) -> bool {
+        if self.is_halted() {
+            return false;
+        }
+
+        self.cycle += 1;
+        for observer in observers.iter_mut() {
+            observer.during_cycle(self.cycle, self);
+        }
+
+        let instruction = &self.program[self.pc];
+        self.elapsed += 1;
+        if self.elapsed >= instruction.opcode.cycles {
+            (instruction.opcode.execute)(&mut self.registers, &instruction.args);
+            self.pc += 1;
+            self.elapsed = 0;
+        }
+        true
+    }
+
+    // Run until the current instruction finishes
+    pub fn step(&mut self, observers: &mut [&mut dyn Observer]) -> bool {
+        let pc = self.pc;
+        while self.pc == pc {
+            if !self.tick(observers) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn run(&mut self, observers: &mut [&mut dyn Observer]) {
+        while self.step(observers) {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn program(lines: &[&str]) -> Vec<Instruction> {
+        lines.iter().map(|line| Instruction::parse(line).expect("Parse failed")).collect()
+    }
+
+    #[test]
+    fn test_d10_cpu() {
+        let y = Register::parse("y").expect("No register y");
+        let mut cpu = Cpu::new(program(&["set y, 5", "add y, x", "add x,y", "addx -2", "set y -3", "add y, 10"]));
+        assert_eq!((cpu.register(Register::X), cpu.register(y)), (1, 0));
+        assert!(cpu.step(&mut []));
+        assert_eq!((cpu.cycle(), cpu.register(y)), (1, 5));
+        assert!(cpu.step(&mut []));
+        assert_eq!((cpu.cycle(), cpu.register(y)), (3, 6));
+        cpu.run(&mut []);
+        assert_eq!((cpu.cycle(), cpu.register(Register::X), cpu.register(y)), (10, 5, 7));
+        assert_eq!(cpu.program()[2].to_string(), "add x, y");
+        assert_eq!(Register::all().map(|r| r.name()).collect::<Vec<&str>>(), vec!["x", "y"]);
+
+        assert_eq!(Instruction::parse("add 3 x").err(), Some(String::from("Unknown register 3")));
+        assert_eq!(Instruction::parse("set y z").err(), Some(String::from("set param must be a number")));
+        assert_eq!(Instruction::parse("addx x").err(), Some(String::from("addx param must be a number")));
+        assert_eq!(Instruction::parse("add y").err(), Some(String::from("Invalid add param count, expected 2")));
+    }
+}
@@ -0,0 +1,353 @@
+use std::io::{Read, BufReader, BufRead, Write};
+
+use super::{parse, Crt, CrtConfig, SignalStrength};
+use super::cpu::{Cpu, Instruction, Register};
+
+enum Breakpoint {
+    Cycle(u32),
+    Instruction(usize),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterEqual),
+            "==" => Ok(Comparison::Equal),
+            "!=" => Ok(Comparison::NotEqual),
+            _ => Err(format!("Invalid comparison {text}")),
+        }
+    }
+
+    fn matches(self, left: i32, right: i32) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+struct Watchpoint {
+    text: String,
+    register: Register,
+    comparison: Comparison,
+    value: i32,
+    triggered: bool, // Only stop when the condition starts to hold
+}
+
+impl Watchpoint {
+    fn parse(text: &str) -> Result<Self, String> {
+        let parts = text.split_whitespace().collect::<Vec<&str>>();
+        if parts.len() != 3 {
+            return Err(format!("Invalid watch expression '{text}', expected e.g. 'x > 38'"));
+        }
+        let value = match parts[2].parse::<i32>() {
+            Ok(value) => value,
+            Err(_) => return Err(format!("Not a number: {}", parts[2])),
+        };
+        Ok(Self {
+            text: parts.join(" "),
+            register: Register::parse(parts[0])?,
+            comparison: Comparison::parse(parts[1])?,
+            value,
+            triggered: false,
+        })
+    }
+}
+
+struct Debugger {
+    cpu: Cpu,
+    crt: Crt,
+    signal: SignalStrength,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    stopped_at: Option<u32>, // Cycle count when execution last stopped
+}
+
+const HELP: &str = "Commands:
+  break cycle N       stop before cycle N is executed
+  break instr N       stop before instruction N (0-based) is executed
+  watch x > 38        stop when the condition starts to hold (<, <=, >, >=, ==, !=)
+  delete              remove all breakpoints and watchpoints
+  tick [N]            execute N cycles (default 1)
+  step [N]            execute N instructions (default 1)
+  continue            run until a breakpoint, watchpoint or end of program
+  regs                print registers
+  crt                 print the screen drawn so far
+  quit                exit the debugger";
+
+impl Debugger {
+    fn new(program: Vec<Instruction>, config: CrtConfig) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            crt: Crt::new(config),
+            signal: SignalStrength::new(config),
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            stopped_at: None,
+        }
+    }
+
+    fn tick(&mut self) -> bool {
+        self.cpu.tick(&mut [&mut self.crt, &mut self.signal])
+    }
+
+    // Returns description of the watchpoint which started to hold
+    fn check_watchpoints(&mut self) -> Option<String> {
+        for watchpoint in self.watchpoints.iter_mut() {
+            let holds = watchpoint.comparison.matches(self.cpu.register(watchpoint.register), watchpoint.value);
+            let fire = holds && !watchpoint.triggered;
+            watchpoint.triggered = holds;
+            if fire {
+                return Some(format!("Watchpoint '{}' hit", watchpoint.text));
+            }
+        }
+        None
+    }
+
+    // Returns description of the breakpoint set on the next cycle or instruction
+    fn check_breakpoints(&self) -> Option<String> {
+        for breakpoint in self.breakpoints.iter() {
+            match breakpoint {
+                Breakpoint::Cycle(cycle) if self.cpu.cycle() + 1 == *cycle => {
+                    return Some(format!("Breakpoint at cycle {cycle} hit"));
+                },
+                Breakpoint::Instruction(index) if self.cpu.pc() == *index && self.cpu.at_instruction_start() => {
+                    return Some(format!("Breakpoint at instruction {index} hit"));
+                },
+                _ => {},
+            }
+        }
+        None
+    }
+
+    fn run_until_stop<F: Fn(&Cpu) -> bool>(&mut self, done: F) -> String {
+        let reason = self.next_stop(done);
+        self.stopped_at = Some(self.cpu.cycle());
+        reason
+    }
+
+    // Breakpoints are checked before every cycle, except the one execution is already stopped at
+    fn next_stop<F: Fn(&Cpu) -> bool>(&mut self, done: F) -> String {
+        if self.stopped_at != Some(self.cpu.cycle()) {
+            if let Some(reason) = self.check_breakpoints() {
+                return reason;
+            }
+        }
+        loop {
+            if !self.tick() {
+                return String::from("Program finished");
+            }
+            if let Some(reason) = self.check_watchpoints().or_else(|| self.check_breakpoints()) {
+                return reason;
+            }
+            if done(&self.cpu) {
+                return String::new();
+            }
+        }
+    }
+
+    fn registers(&self) -> String {
+        Register::all()
+            .map(|r| format!("{} = {}", r.name(), self.cpu.register(r)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn status(&self) -> String {
+        let next = match self.cpu.current_instruction() {
+            Some(instruction) => format!("instr {} ({})", self.cpu.pc(), instruction),
+            None => String::from("halted"),
+        };
+        let sprite_x = self.cpu.register(Register::X);
+        let sprite = (0..self.crt.config.width)
+            .map(|column| if self.crt.config.sprite_covers(column, sprite_x) { '^' } else { ' ' })
+            .collect::<String>();
+        format!(
+            "cycle {}, next {}, {}, signal {}\n{}\n{}",
+            self.cpu.cycle(),
+            next,
+            self.registers(),
+            self.signal.checksum,
+            self.crt.current_row(),
+            sprite.trim_end(),
+        )
+    }
+
+    fn count(arg: Option<&str>) -> Result<u32, String> {
+        match arg {
+            Some(text) => match text.parse::<u32>() {
+                Ok(num) => Ok(num),
+                Err(_) => Err(format!("Not a number: {text}")),
+            },
+            None => Ok(1),
+        }
+    }
+
+    // Returns None when the debugger should exit
+    fn execute(&mut self, line: &str) -> Result<Option<String>, String> {
+        let mut parts = line.split_whitespace();
+        let output = match parts.next() {
+            None => String::new(),
+            Some("help" | "h") => String::from(HELP),
+            Some("quit" | "q") => return Ok(None),
+            Some("break" | "b") => {
+                let kind = parts.next();
+                let num = match parts.next().map(|n| n.parse::<u32>()) {
+                    Some(Ok(num)) => num,
+                    _ => return Err(String::from("break expects a number")),
+                };
+                match kind {
+                    Some("cycle") => self.breakpoints.push(Breakpoint::Cycle(num)),
+                    Some("instr") => self.breakpoints.push(Breakpoint::Instruction(num as usize)),
+                    _ => return Err(String::from("break expects 'cycle' or 'instr'")),
+                }
+                format!("Breakpoint {} set", self.breakpoints.len())
+            },
+            Some("watch" | "w") => {
+                let mut watchpoint = Watchpoint::parse(&parts.collect::<Vec<&str>>().join(" "))?;
+                watchpoint.triggered = watchpoint.comparison.matches(self.cpu.register(watchpoint.register), watchpoint.value);
+                let text = format!("Watchpoint '{}' set", watchpoint.text);
+                self.watchpoints.push(watchpoint);
+                text
+            },
+            Some("delete" | "d") => {
+                self.breakpoints.clear();
+                self.watchpoints.clear();
+                String::from("All breakpoints and watchpoints deleted")
+            },
+            Some("tick" | "t") => {
+                let mut reason = String::new();
+                for _ in 0..Self::count(parts.next())? {
+                    reason = self.run_until_stop(|_| true);
+                    if !reason.is_empty() {
+                        break;
+                    }
+                }
+                format!("{reason}\n{}", self.status())
+            },
+            Some("step" | "s") => {
+                let mut reason = String::new();
+                for _ in 0..Self::count(parts.next())? {
+                    reason = self.run_until_stop(|cpu| cpu.at_instruction_start());
+                    if !reason.is_empty() {
+                        break;
+                    }
+                }
+                format!("{reason}\n{}", self.status())
+            },
+            Some("continue" | "c") => {
+                let reason = self.run_until_stop(|_| false);
+                format!("{reason}\n{}", self.status())
+            },
+            Some("regs" | "r") => self.registers(),
+            Some("crt") => self.crt.screen(),
+            Some(cmd) => return Err(format!("Unknown command {cmd}, try 'help'")),
+        };
+        Ok(Some(output.trim_start().to_string()))
+    }
+}
+
+pub fn debug<R: Read, C: BufRead, W: Write>(input: BufReader<R>, commands: C, mut output: W) -> Result<(), String> {
+    let program = parse(input).collect::<Result<Vec<Instruction>, String>>()?;
+    let mut debugger = Debugger::new(program, CrtConfig::default());
+
+    let write_err = |e: std::io::Error| format!("Unable to write output: {e}");
+    write!(output, "{}\n(dbg) ", debugger.status()).map_err(write_err)?;
+    output.flush().map_err(write_err)?;
+    for line in commands.lines() {
+        let line = match line {
+            Ok(ln) => ln,
+            Err(e) => return Err(format!("Unable to read command: {e}")),
+        };
+        let text = match debugger.execute(&line) {
+            Ok(Some(text)) => text,
+            Ok(None) => break,
+            Err(e) => format!("Error: {e}"),
+        };
+        if !text.is_empty() {
+            writeln!(output, "{text}").map_err(write_err)?;
+        }
+        write!(output, "(dbg) ").map_err(write_err)?;
+        output.flush().map_err(write_err)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::str_to_buf_reader;
+
+    #[test]
+    fn test_d10_debugger() {
+        let program = String::from("noop
+addx 3
+addx -5
+noop
+addx 40");
+        let mut debugger = Debugger::new(parse(str_to_buf_reader(&program)).collect::<Result<Vec<Instruction>, String>>().unwrap(), CrtConfig::default());
+
+        debugger.execute("break cycle 4").unwrap();
+        let out = debugger.execute("continue").unwrap().unwrap();
+        assert!(out.starts_with("Breakpoint at cycle 4 hit\ncycle 3, next instr 2 (addx -5), x = 4"), "{out}");
+        assert!(out.ends_with("\n###\n   ^^^"), "{out}");
+
+        debugger.execute("delete").unwrap();
+        debugger.execute("break instr 3").unwrap();
+        let out = debugger.execute("c").unwrap().unwrap();
+        assert!(out.starts_with("Breakpoint at instruction 3 hit\ncycle 5, next instr 3 (noop), x = -1"), "{out}");
+
+        debugger.execute("watch x > 38").unwrap();
+        let out = debugger.execute("step 5").unwrap().unwrap();
+        assert!(out.starts_with("Watchpoint 'x > 38' hit\ncycle 8, next halted, x = 39"), "{out}");
+
+        assert_eq!(debugger.execute("regs").unwrap(), Some(String::from("x = 39, y = 0")));
+        assert!(debugger.execute("watch x >").is_err());
+        assert!(debugger.execute("break line 3").is_err());
+        assert_eq!(debugger.execute("quit").unwrap(), None);
+    }
+
+    #[test]
+    fn test_d10_debugger_first_cycle() {
+        let program = String::from("addx 3\nnoop\naddx 1");
+        let load = || Debugger::new(parse(str_to_buf_reader(&program)).collect::<Result<Vec<Instruction>, String>>().unwrap(), CrtConfig::default());
+
+        let mut debugger = load();
+        debugger.execute("break cycle 1").unwrap();
+        let out = debugger.execute("c").unwrap().unwrap();
+        assert!(out.starts_with("Breakpoint at cycle 1 hit\ncycle 0, next instr 0 (addx 3), x = 1"), "{out}");
+        // Continuing leaves the position it is stopped at
+        let out = debugger.execute("c").unwrap().unwrap();
+        assert!(out.starts_with("Program finished\ncycle 5"), "{out}");
+
+        let mut debugger = load();
+        debugger.execute("break instr 0").unwrap();
+        debugger.execute("break instr 1").unwrap();
+        let out = debugger.execute("c").unwrap().unwrap();
+        assert!(out.starts_with("Breakpoint at instruction 0 hit\ncycle 0"), "{out}");
+        let out = debugger.execute("c").unwrap().unwrap();
+        assert!(out.starts_with("Breakpoint at instruction 1 hit\ncycle 2, next instr 1 (noop), x = 4"), "{out}");
+        // Stepping onto a breakpoint reports it
+        let mut debugger = load();
+        debugger.execute("break cycle 3").unwrap();
+        let out = debugger.execute("step").unwrap().unwrap();
+        assert!(out.starts_with("Breakpoint at cycle 3 hit\ncycle 2"), "{out}");
+    }
+}
@@ -1,119 +1,136 @@
 use std::{io::{Read, BufReader, BufRead}};
 
-enum Command {
-    Noop,
-    Addx { x: i32 },
-}
+mod assembler;
+mod cpu;
+mod debugger;
+
+pub use assembler::{assemble, disassemble};
+pub use debugger::debug;
+use cpu::{Cpu, Instruction, Observer, Register};
 
-fn parse<R: Read>(input: BufReader<R>) -> impl Iterator<Item=Result<Command, String>> {
+fn parse<R: Read>(input: BufReader<R>) -> impl Iterator<Item=Result<Instruction, String>> {
     input.lines().map(|line| {
         match line {
-            Ok(ln) => {
-                let mut parts = ln.split(' ');
-                match parts.next() {
-                    Some("noop") => Ok(Command::Noop),
-                    Some("addx") => match parts.next() {
-                        Some(num) => match num.parse::<i32>() {
-                            Ok(x) => Ok(Command::Addx { x }),
-                            Err(_) => Err(format!("addx param must be a number")),
-                        }
-                        None => Err(format!("Invalid addx param")),
-                    },
-                    Some(cmd) => Err(format!("Invalid command {cmd}")),
-                    None => Err(format!("Invalid line {ln}")),
-                }
-            },
+            Ok(ln) => Instruction::parse(&ln),
             Err(e) => Err(format!("Unable to read line: {e}")),
         }
     })
 }
 
-struct Display {
-    d: String,
-    checksum: i32,
-    register: i32,
-    cycle: i32,
+#[derive(Debug, Clone, Copy)]
+struct CrtConfig {
+    width: i32,
+    height: i32,
+    sprite_width: i32,
+    checksum_offset: i32,
+    checksum_stride: i32,
 }
 
-const WIDTH: i32 = 40;
-const CHECKSUM_OFFSET: i32 = 20;
-
-impl Display {
-    fn new() -> Self {
+impl Default for CrtConfig {
+    fn default() -> Self {
         Self {
-            d: String::new(),
-            checksum: 0i32,
-            register: 1,
-            cycle: 0,
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+            checksum_offset: 20,
+            checksum_stride: 40,
         }
     }
+}
 
-    fn checksum_increase(&mut self) {
-        if (self.cycle - CHECKSUM_OFFSET) % WIDTH == 0 {
-            self.checksum += self.cycle * self.register;
-        }
+impl CrtConfig {
+    // Sprite is centered on the register, wider half goes right for even widths
+    fn sprite_covers(&self, column: i32, register: i32) -> bool {
+        let left = register - (self.sprite_width - 1) / 2;
+        column >= left && column < left + self.sprite_width
     }
 
-    fn draw(&mut self) {
-        println!("Cycle {}, register: {}", self.cycle, self.register);
-        self.cycle += 1;
-
-        let pos = self.cycle % WIDTH;
+    // Cycles are numbered from 1, sprite doesn't wrap to neighbouring rows
+    fn pixel_lit(&self, cycle: i32, register: i32) -> bool {
+        self.sprite_covers((cycle - 1) % self.width, register)
+    }
+}
 
-        let mut on = false;
-        // FIXME: end of line handling is probably invalid
-        if pos == self.register {
-            on = true;
-        } else if pos == self.register + 1 {
-            on = true;
-        } else if pos == self.register + 2 {
-            on = true;
-        }
+struct Crt {
+    config: CrtConfig,
+    pixels: Vec<bool>,
+    cycles: usize,
+}
 
-        if on {
-            self.d.push('#');
-        } else {
-            self.d.push('.');
+impl Crt {
+    fn new(config: CrtConfig) -> Self {
+        Self {
+            config,
+            pixels: vec![false; (config.width * config.height) as usize],
+            cycles: 0,
         }
+    }
 
-        if self.cycle % WIDTH == 0 {
-            self.d.push('\n');
-        }
+    fn row_to_string(&self, row: usize, len: usize) -> String {
+        let start = row * self.config.width as usize;
+        self.pixels[start..start + len].iter().map(|lit| if *lit { '#' } else { '.' }).collect()
+    }
 
-        self.checksum_increase();
+    // Rows drawn so far, starts over from the top once the screen is full
+    fn screen(&self) -> String {
+        let rows = self.cycles.div_ceil(self.config.width as usize).min(self.config.height as usize);
+        (0..rows).map(|row| self.row_to_string(row, self.config.width as usize) + "\n").collect()
+    }
 
+    // Part of the row that is being drawn right now
+    fn current_row(&self) -> String {
+        let width = self.config.width as usize;
+        self.row_to_string((self.cycles / width) % self.config.height as usize, self.cycles % width)
     }
+}
 
-    fn add(&mut self, num: i32) {
-        self.register += num;
+impl Observer for Crt {
+    fn during_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+        let index = (cycle as usize - 1) % self.pixels.len();
+        self.pixels[index] = self.config.pixel_lit(cycle as i32, cpu.register(Register::X));
+        self.cycles = cycle as usize;
     }
 }
 
+struct SignalStrength {
+    config: CrtConfig,
+    checksum: i32,
+}
 
-pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
-    let mut display = Display::new();
+impl SignalStrength {
+    fn new(config: CrtConfig) -> Self {
+        Self {
+            config,
+            checksum: 0,
+        }
+    }
+}
 
-    for command in parse(input) {
-        match command {
-            Ok(cmd) => {
-                match cmd {
-                    Command::Noop => {
-                        display.draw();
-                    }
-                    Command::Addx { x } => {
-                        display.draw();
-                        display.draw();
-                        display.add(x);
-                    }
-                };
-            },
-            Err(e) => return Err(e),
+impl Observer for SignalStrength {
+    fn during_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+        let cycle = cycle as i32;
+        let offset = cycle - self.config.checksum_offset;
+        if offset >= 0 && offset % self.config.checksum_stride == 0 {
+            self.checksum += cycle * cpu.register(Register::X);
         }
     }
+}
+
+fn run_with_config(program: Vec<Instruction>, config: CrtConfig) -> (i32, String) {
+    let mut crt = Crt::new(config);
+    let mut signal = SignalStrength::new(config);
+    let mut cpu = Cpu::new(program);
+    cpu.run(&mut [&mut crt, &mut signal]);
+    (signal.checksum, crt.screen())
+}
+
+pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
+    let program = parse(input).collect::<Result<Vec<Instruction>, String>>()?;
+    let (checksum, screen) = run_with_config(program, CrtConfig::default());
 
     Ok((
-        format!("{}", display.checksum),
-        format!("\n{}", display.d),
+        format!("{}", checksum),
+        format!("\n{}", screen),
     ))
 }
 
@@ -282,12 +299,65 @@ noop");
 ###...###...###...###...###...###...###.
 ####....####....####....####....####....
 #####.....#####.....#####.....#####.....
-######......######......######......###.
+######......######......######......####
 #######.......#######.......#######.....
 ",
         );
     }
 
+    struct Recorder {
+        values: Vec<(u32, i32)>,
+    }
+
+    impl Observer for Recorder {
+        fn during_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+            self.values.push((cycle, cpu.register(Register::X)));
+        }
+    }
+
+    #[test]
+    fn test_d10_cpu() {
+        let input = String::from("noop
+addx 3
+addx -5");
+        let program = parse(str_to_buf_reader(&input)).collect::<Result<Vec<Instruction>, String>>().expect("Parse failed");
+        let mut recorder = Recorder { values: Vec::new() };
+        let mut cpu = Cpu::new(program);
+        cpu.run(&mut [&mut recorder]);
+
+        assert_eq!(recorder.values, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.register(Register::X), -1);
+        assert!(cpu.is_halted());
+        assert!(!cpu.step(&mut [&mut recorder]));
+    }
+
+    #[test]
+    fn test_d10_geometry() {
+        let input = String::from("addx 2
+addx 1
+noop
+addx -4
+noop
+noop
+noop
+addx 5");
+        let program = || parse(str_to_buf_reader(&input)).collect::<Result<Vec<Instruction>, String>>().expect("Parse failed");
+
+        // Register during cycles 1..=12: 1 1 3 3 4 4 4 0 0 0 0 0
+        let config = CrtConfig { width: 4, height: 3, sprite_width: 3, checksum_offset: 2, checksum_stride: 3 };
+        let (checksum, screen) = run_with_config(program(), config);
+        // Cycles 2, 5, 8 and 11, register is 0 in the last two
+        assert_eq!(checksum, 2 + 5 * 4);
+        // Sprite at x = 4 is past the end of the row, it must not wrap to the start of the next one
+        assert_eq!(screen, "####\n....\n##..\n");
+
+        let config = CrtConfig { width: 3, height: 2, sprite_width: 4, checksum_offset: 7, checksum_stride: 100 };
+        let (checksum, screen) = run_with_config(program(), config);
+        assert_eq!(checksum, 7 * 4);
+        // Screen is full after 6 cycles and starts over from the top
+        assert_eq!(screen, ".##\n###\n");
+    }
+
     #[test]
     fn test_d10_final() {
         let f = std::fs::File::open("src/d10/input.txt").expect("No src/10/input.txt file");
@@ -300,10 +370,10 @@ noop");
             result.1,
             "
 ###...##..#....###..###..####..##..#..#.
-#..#.#..#.#....#..#.#..#....#.#..#.#..##
+#..#.#..#.#....#..#.#..#....#.#..#.#..#.
 #..#.#....#....#..#.###....#..#..#.#..#.
-###..#.##.#....###..#..#..#...####.#..##
-#.#..#..#.#....#.#..#..#.#....#..#.#..##
+###..#.##.#....###..#..#..#...####.#..#.
+#.#..#..#.#....#.#..#..#.#....#..#.#..#.
 #..#..###.####.#..#.###..####.#..#..##..
 ",
         );
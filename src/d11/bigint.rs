@@ -0,0 +1,244 @@
+use std::{cmp::Ordering, fmt};
+
+use super::expr::Number;
+
+// Signed arbitrary precision integer, magnitude is stored as little endian base 2^32 digits
+// without trailing zeros (zero has no digits and is never negative)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// Expects a >= b
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, digit) in a.iter().enumerate() {
+        let mut diff = *digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        }
+        result.push(diff as u32);
+    }
+    trim(&mut result);
+    result
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let current = result[i + j] as u64 + *x as u64 * *y as u64 + carry;
+            result[i + j] = current as u32;
+            carry = current >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    trim(&mut result);
+    result
+}
+
+fn divmod_small(a: &[u32], b: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for i in (0..a.len()).rev() {
+        let current = (remainder << 32) | a[i] as u64;
+        quotient[i] = (current / b as u64) as u32;
+        remainder = current % b as u64;
+    }
+    trim(&mut quotient);
+    (quotient, remainder as u32)
+}
+
+// Expects non-empty b; divisors are mostly small, so the bit by bit variant is only a fallback
+fn divmod_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if b.len() == 1 {
+        let (quotient, remainder) = divmod_small(a, b[0]);
+        let mut remainder = vec![remainder];
+        trim(&mut remainder);
+        return (quotient, remainder);
+    }
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        remainder = add_mag(&remainder, &remainder);
+        if (a[bit / 32] >> (bit % 32)) & 1 == 1 {
+            remainder = add_mag(&remainder, &[1]);
+        }
+        trim(&mut remainder);
+        if cmp_mag(&remainder, b) != Ordering::Less {
+            remainder = sub_mag(&remainder, b);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        trim(&mut digits);
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    // Euclidean division, remainder is never negative
+    fn div_rem_euclid(&self, other: &BigInt) -> Result<(BigInt, BigInt), String> {
+        if other.digits.is_empty() {
+            return Err(String::from("Division by zero"));
+        }
+        let (quotient, remainder) = divmod_mag(&self.digits, &other.digits);
+        if !self.negative || remainder.is_empty() {
+            return Ok((
+                BigInt::new(self.negative != other.negative, quotient),
+                BigInt::new(false, remainder),
+            ));
+        }
+        Ok((
+            BigInt::new(!other.negative, add_mag(&quotient, &[1])),
+            BigInt::new(false, sub_mag(&other.digits, &remainder)),
+        ))
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(num: u128) -> Self {
+        BigInt::new(false, (0..4).map(|i| (num >> (32 * i)) as u32).collect())
+    }
+}
+
+impl Number for BigInt {
+    fn from_i64(num: i64) -> Self {
+        let magnitude = num.unsigned_abs();
+        BigInt::new(num < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    fn add(&self, other: &Self) -> Result<Self, String> {
+        if self.negative == other.negative {
+            return Ok(BigInt::new(self.negative, add_mag(&self.digits, &other.digits)));
+        }
+        Ok(match cmp_mag(&self.digits, &other.digits) {
+            Ordering::Less => BigInt::new(other.negative, sub_mag(&other.digits, &self.digits)),
+            _ => BigInt::new(self.negative, sub_mag(&self.digits, &other.digits)),
+        })
+    }
+
+    fn sub(&self, other: &Self) -> Result<Self, String> {
+        self.add(&BigInt::new(!other.negative, other.digits.clone()))
+    }
+
+    fn mul(&self, other: &Self) -> Result<Self, String> {
+        Ok(BigInt::new(self.negative != other.negative, mul_mag(&self.digits, &other.digits)))
+    }
+
+    fn div_euclid(&self, other: &Self) -> Result<Self, String> {
+        Ok(self.div_rem_euclid(other)?.0)
+    }
+
+    fn rem_euclid(&self, other: &Self) -> Result<Self, String> {
+        Ok(self.div_rem_euclid(other)?.1)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+        // Split into base 10^9 chunks, least significant first
+        let mut chunks = Vec::new();
+        let mut rest = self.digits.clone();
+        while !rest.is_empty() {
+            let (quotient, remainder) = divmod_small(&rest, 1_000_000_000);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks[chunks.len() - 1])?;
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_d11_bigint() {
+        let big = |n: i64| BigInt::from_i64(n);
+        let values = [0, 1, -1, 7, -7, 12345, -99999, i64::MAX, i64::MIN + 1, 1 << 40, -(1 << 33) + 5];
+        for a in values {
+            for b in values {
+                let (x, y) = (a as i128, b as i128);
+                assert_eq!(big(a).add(&big(b)).unwrap().to_string(), (x + y).to_string());
+                assert_eq!(big(a).sub(&big(b)).unwrap().to_string(), (x - y).to_string());
+                assert_eq!(big(a).mul(&big(b)).unwrap().to_string(), (x * y).to_string());
+                if b != 0 {
+                    assert_eq!(big(a).div_euclid(&big(b)).unwrap().to_string(), x.div_euclid(y).to_string(), "{a} / {b}");
+                    assert_eq!(big(a).rem_euclid(&big(b)).unwrap().to_string(), x.rem_euclid(y).to_string(), "{a} % {b}");
+                } else {
+                    assert!(big(a).div_euclid(&big(b)).is_err());
+                }
+            }
+        }
+
+        assert_eq!(BigInt::from(u128::MAX).to_string(), u128::MAX.to_string());
+
+        // 3^100 and its division by 3^60
+        let mut power = big(1);
+        for _ in 0..100 {
+            power = power.mul(&big(3)).unwrap();
+        }
+        assert_eq!(power.to_string(), "515377520732011331036461129765621272702107522001");
+        let mut divisor = big(1);
+        for _ in 0..60 {
+            divisor = divisor.mul(&big(3)).unwrap();
+        }
+        let (quotient, remainder) = power.div_rem_euclid(&divisor).unwrap();
+        assert_eq!(quotient.to_string(), "12157665459056928801");
+        assert!(remainder.is_zero());
+        assert_eq!(power.add(&big(1)).unwrap().rem_euclid(&divisor).unwrap(), big(1));
+    }
+}
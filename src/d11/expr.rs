@@ -0,0 +1,250 @@
+// Worry level representation, either machine integer or arbitrary precision one
+pub trait Number: Sized + Clone {
+    fn from_i64(num: i64) -> Self;
+    fn is_zero(&self) -> bool;
+    fn add(&self, other: &Self) -> Result<Self, String>;
+    fn sub(&self, other: &Self) -> Result<Self, String>;
+    fn mul(&self, other: &Self) -> Result<Self, String>;
+    fn div_euclid(&self, other: &Self) -> Result<Self, String>;
+    fn rem_euclid(&self, other: &Self) -> Result<Self, String>;
+}
+
+fn checked(result: Option<i64>, a: i64, op: &str, b: i64) -> Result<i64, String> {
+    match result {
+        Some(r) => Ok(r),
+        None if b == 0 && (op == "/" || op == "%") => Err(String::from("Division by zero")),
+        None => Err(format!("Worry level overflow in {a} {op} {b}")),
+    }
+}
+
+impl Number for i64 {
+    fn from_i64(num: i64) -> Self {
+        num
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn add(&self, other: &Self) -> Result<Self, String> {
+        checked(self.checked_add(*other), *self, "+", *other)
+    }
+
+    fn sub(&self, other: &Self) -> Result<Self, String> {
+        checked(self.checked_sub(*other), *self, "-", *other)
+    }
+
+    fn mul(&self, other: &Self) -> Result<Self, String> {
+        checked(self.checked_mul(*other), *self, "*", *other)
+    }
+
+    fn div_euclid(&self, other: &Self) -> Result<Self, String> {
+        checked(self.checked_div_euclid(*other), *self, "/", *other)
+    }
+
+    fn rem_euclid(&self, other: &Self) -> Result<Self, String> {
+        checked(self.checked_rem_euclid(*other), *self, "%", *other)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Num(i64),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Old,
+    Num(i64),
+    Op(Op),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            ' ' => continue,
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '%' => Token::Op(Op::Mod),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() => {
+                let mut num = String::from(c);
+                while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                    num.push(d);
+                }
+                match num.parse::<i64>() {
+                    Ok(n) => Token::Num(n),
+                    Err(_) => return Err(format!("Number too big: {num}")),
+                }
+            },
+            c if c.is_ascii_alphabetic() => {
+                let mut word = String::from(c);
+                while let Some(d) = chars.next_if(|d| d.is_ascii_alphanumeric()) {
+                    word.push(d);
+                }
+                match word.as_str() {
+                    "old" => Token::Old,
+                    _ => return Err(format!("Unknown variable {word}")),
+                }
+            },
+            c => return Err(format!("Unexpected character '{c}'")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        while let Some(Token::Op(op @ (Op::Add | Op::Sub))) = self.peek() {
+            self.pos += 1;
+            left = Expr::Binary(Box::new(left), op, Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    // term := factor (('*' | '/' | '%') factor)*
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.factor()?;
+        while let Some(Token::Op(op @ (Op::Mul | Op::Div | Op::Mod))) = self.peek() {
+            self.pos += 1;
+            left = Expr::Binary(Box::new(left), op, Box::new(self.factor()?));
+        }
+        Ok(left)
+    }
+
+    // factor := 'old' | number | '-' factor | '(' expr ')'
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Old) => Ok(Expr::Old),
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Op(Op::Sub)) => Ok(Expr::Binary(Box::new(Expr::Num(0)), Op::Sub, Box::new(self.factor()?))),
+            Some(Token::Open) => {
+                let inner = self.expr()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(String::from("Expected ')'")),
+                }
+            },
+            Some(token) => Err(format!("Unexpected {token:?}")),
+            None => Err(String::from("Unexpected end of expression")),
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(text)?, pos: 0 };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {token:?} after expression")),
+        }
+    }
+
+    pub fn eval<N: Number>(&self, old: &N) -> Result<N, String> {
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Num(n) => Ok(N::from_i64(*n)),
+            Expr::Binary(left, op, right) => {
+                let (a, b) = (left.eval(old)?, right.eval(old)?);
+                match op {
+                    Op::Add => a.add(&b),
+                    Op::Sub => a.sub(&b),
+                    Op::Mul => a.mul(&b),
+                    Op::Div => a.div_euclid(&b),
+                    Op::Mod => a.rem_euclid(&b),
+                }
+            },
+        }
+    }
+
+    // Result modulo M can be computed from `old` modulo M only when the expression consists
+    // of +, - and * and of `% n` with positive constant `n` dividing M. Returns such constants, or
+    // None when the expression can't be reduced at all (division, modulo by non-constant or n <= 0).
+    pub fn modular_constants(&self) -> Option<Vec<i64>> {
+        match self {
+            Expr::Old | Expr::Num(_) => Some(Vec::new()),
+            Expr::Binary(_, Op::Div, _) => None,
+            Expr::Binary(left, Op::Mod, right) => match right.as_ref() {
+                Expr::Num(n) if *n > 0 => {
+                    let mut constants = left.modular_constants()?;
+                    constants.push(*n);
+                    Some(constants)
+                },
+                _ => None,
+            },
+            Expr::Binary(left, _, right) => {
+                let mut constants = left.modular_constants()?;
+                constants.extend(right.modular_constants()?);
+                Some(constants)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_d11_expr() {
+        let eval = |text: &str, old: i64| Expr::parse(text).and_then(|e| e.eval(&old));
+        assert_eq!(eval("old * 19", 2), Ok(38));
+        assert_eq!(eval("old * old + 3", 4), Ok(19));
+        assert_eq!(eval("(old + 2) * 5", 4), Ok(30));
+        assert_eq!(eval("old - 2 - 3", 10), Ok(5));
+        assert_eq!(eval("old / 2 * 3 % 4", 7), Ok(1));
+        assert_eq!(eval("-(old + 1) % 5", 1), Ok(3));
+        assert!(eval("old / (old - 1)", 1).is_err());
+        assert!(eval("old * old", i64::MAX).is_err());
+
+        assert!(Expr::parse("(old + 1").is_err());
+        assert!(Expr::parse("old +").is_err());
+        assert!(Expr::parse("old 2").is_err());
+        assert!(Expr::parse("new * 2").is_err());
+
+        let constants = |text: &str| Expr::parse(text).unwrap().modular_constants();
+        assert_eq!(constants("(old + 2) * old - 1"), Some(vec![]));
+        assert_eq!(constants("old % 7 + (old * 3) % 5"), Some(vec![7, 5]));
+        assert_eq!(constants("old / 2"), None);
+        assert_eq!(constants("old % old"), None);
+        assert_eq!(constants("old % 0"), None);
+        assert_eq!(constants("old % -3"), None);
+    }
+}
@@ -1,98 +1,95 @@
 use std::{io::{Read, BufReader, BufRead}};
 
-#[derive(Debug, Clone)]
-enum Operation {
-    Plus,
-    Multiply,
-}
+mod bigint;
+mod expr;
+mod report;
+mod validate;
+
+use bigint::BigInt;
+use expr::{Expr, Number};
+
+pub use report::{report_csv, report_text};
+use validate::{validate, warnings};
 
+// Monkey definition, shared by all simulations
 #[derive(Debug)]
-struct Monkey {
+struct MonkeyDef {
+    id: usize,
+    items: Vec<i64>,
+    operation: Expr,
+    divisible_by: i64,
+    true_monkey: usize,
+    false_monkey: usize,
+}
+
+// Monkey definition while it's being parsed
+struct PartialMonkey {
     id: Option<usize>,
-    items1: Vec<i64>,
-    items2: Vec<i64>,
-    operation: Option<Operation>,
-    operand1: Option<i64>, // None means old
-    operand2: Option<i64>, // None means old
+    items: Vec<i64>,
+    operation: Option<Expr>,
     divisible_by: Option<i64>,
     true_monkey: Option<usize>,
     false_monkey: Option<usize>,
-    inspected_count1: usize,
-    inspected_count2: usize,
 }
 
-impl Monkey {
-    fn new() -> Self {
+impl PartialMonkey {
+    fn new(id: usize) -> Self {
         Self {
-            id: None,
-            items1: Vec::new(),
-            items2: Vec::new(),
+            id: Some(id),
+            items: Vec::new(),
             operation: None,
-            operand1: None,
-            operand2: None,
             divisible_by: None,
             true_monkey: None,
             false_monkey: None,
-            inspected_count1: 0,
-            inspected_count2: 0,
         }
     }
 
-    fn inspect(self: &mut Self, is_first: bool, worry: i64, divider: i64, max_divisible: i64) -> i64 {
-        match is_first {
-            true => {
-                self.inspected_count1 += 1;
-            }
-            false => {
-                self.inspected_count2 += 1;
-            }
-        };
-        let op1 = match self.operand1 {
-            Some(x) => x,
-            None => worry,
-        };
-        let op2 = match self.operand2 {
-            Some(x) => x,
-            None => worry,
-        };
-        let result = match self.operation {
-            Some(Operation::Plus) => op1 + op2,
-            Some(Operation::Multiply) => op1 * op2,
-            None => panic!("Operation missing"),
-        };
-        (result / divider) % max_divisible
-    }
-
-    fn worry_test(self: &Self, worry: i64) -> bool {
-        match self.divisible_by {
-            Some(divisible_by) => worry % divisible_by == 0,
-            None => panic!("Divisible by missing"),
+    // Checks all fields were present in the definition
+    fn finish(self) -> Result<MonkeyDef, String> {
+        let id = self.id.unwrap_or_default();
+        match (self.operation, self.divisible_by, self.true_monkey, self.false_monkey) {
+            (Some(operation), Some(divisible_by), Some(true_monkey), Some(false_monkey)) => Ok(MonkeyDef {
+                id,
+                items: self.items,
+                operation,
+                divisible_by,
+                true_monkey,
+                false_monkey,
+            }),
+            (operation, divisible_by, true_monkey, false_monkey) => {
+                let missing = [
+                    ("Operation", operation.is_none()),
+                    ("Test", divisible_by.is_none()),
+                    ("If true", true_monkey.is_none()),
+                    ("If false", false_monkey.is_none()),
+                ].iter().filter(|(_, missing)| *missing).map(|(name, _)| *name).collect::<Vec<&str>>();
+                Err(format!("Monkey {id} is missing: {}", missing.join(", ")))
+            },
         }
     }
-
-    fn add_item1(self: &mut Self, item: i64) {
-        self.items1.push(item);
-    }
-
-    fn add_item2(self: &mut Self, item: i64) {
-        self.items2.push(item);
-    }
 }
 
-fn parse<R: Read>(input: BufReader<R>) -> Result<Vec<Monkey>, String> {
-    let mut monkeys: Vec<Monkey> = Vec::new();
-    let mut current_monkey = Monkey::new();
+fn parse<R: Read>(input: BufReader<R>) -> Result<Vec<MonkeyDef>, String> {
+    let mut monkeys: Vec<MonkeyDef> = Vec::new();
+    let mut current_monkey = PartialMonkey { id: None, ..PartialMonkey::new(0) };
 
     for line in input.lines() {
         match line {
             Ok(ln) => {
                 let mut parts = ln.trim().split(' ');
-                match parts.next() {
+                let first = parts.next();
+                if current_monkey.id.is_none() && !matches!(first, Some("Monkey" | "")) {
+                    return Err(format!("Line outside of monkey definition: {ln}"));
+                }
+                match first {
                     Some("Monkey") => match parts.next() {
                         Some(id) => {
                             match id.trim_end_matches(':').parse::<usize>() {
                                 Ok(id_num) => {
-                                    current_monkey.id = Some(id_num);
+                                    let previous = std::mem::replace(&mut current_monkey, PartialMonkey::new(id_num));
+                                    if previous.id.is_some() {
+                                        monkeys.push(previous.finish()?);
+                                    }
                                 },
                                 Err(_) => return Err(String::from("Monkey has invalid ID")),
                             };
@@ -108,10 +105,7 @@ fn parse<R: Read>(input: BufReader<R>) -> Result<Vec<Monkey>, String> {
                         loop {
                             match parts.next() {
                                 Some(text) => match text.trim_end_matches(',').parse::<i64>() {
-                                    Ok(num) => {
-                                        current_monkey.items1.push(num);
-                                        current_monkey.items2.push(num);
-                                    },
+                                    Ok(num) => current_monkey.items.push(num),
                                     Err(_) => return Err(format!("Not a number: {text}")),
                                 },
                                 None => break,
@@ -127,28 +121,9 @@ fn parse<R: Read>(input: BufReader<R>) -> Result<Vec<Monkey>, String> {
                             Some("=") => {},
                             _ => return Err(format!("Expected '='")),
                         }
-                        current_monkey.operand1 = match parts.next() {
-                            Some("old") => None,
-                            Some(text) => match text.parse::<i64>() {
-                                Ok(num) => Some(num),
-                                Err(_) => return Err(String::from("Not a number")),
-                            },
-                            None => return Err(String::from("Number or 'old' is missing")),
-                        };
-
-                        current_monkey.operation = match parts.next() {
-                            Some("+") => Some(Operation::Plus),
-                            Some("*") => Some(Operation::Multiply),
-                            _ => return Err(format!("Expected '+' or '*'")),
-                        };
-
-                        current_monkey.operand2 = match parts.next() {
-                            Some("old") => None,
-                            Some(text) => match text.parse::<i64>() {
-                                Ok(num) => Some(num),
-                                Err(_) => return Err(String::from("Not a number")),
-                            },
-                            None => return Err(String::from("Number or 'old' is missing")),
+                        current_monkey.operation = match Expr::parse(&parts.collect::<Vec<&str>>().join(" ")) {
+                            Ok(expr) => Some(expr),
+                            Err(e) => return Err(format!("Invalid operation: {e}")),
                         };
                     },
                     Some("Test:") => {
@@ -182,8 +157,6 @@ fn parse<R: Read>(input: BufReader<R>) -> Result<Vec<Monkey>, String> {
                             match monkey.parse::<usize>() {
                                 Ok(num) => {
                                     current_monkey.false_monkey = Some(num);
-                                    monkeys.push(current_monkey);
-                                    current_monkey = Monkey::new();
                                 },
                                 Err(_) => return Err(String::from("Invalid false monkey")),
                             };
@@ -198,90 +171,268 @@ fn parse<R: Read>(input: BufReader<R>) -> Result<Vec<Monkey>, String> {
             Err(e) => return Err(format!("Unable to read line: {e}")),
         }
     }
-    return Ok(monkeys);
+    if current_monkey.id.is_some() {
+        monkeys.push(current_monkey.finish()?);
+    }
+    validate(&monkeys)?;
+    Ok(monkeys)
 }
 
-fn run_for(monkeys: &mut Vec<Monkey>, is_first: bool, rounds: usize, factor: i64, max_divisble: i64) -> Result<usize, String> {
-    for _round in 0..rounds {
-        for monkey_id in 0..monkeys.len() {
-            let items: Vec<i64> = match is_first {
-                true => monkeys[monkey_id].items1.drain(..).collect(),
-                false => monkeys[monkey_id].items2.drain(..).collect(),
-            };
+fn gcd(a: i64, b: i64) -> i64 {
+    match b {
+        0 => a.abs(),
+        _ => gcd(b, a % b),
+    }
+}
 
-            for item in items.iter() {
-                let worry_level = monkeys[monkey_id].inspect(is_first, *item, factor, max_divisble);
-                let target_monkey = match monkeys[monkey_id].worry_test(worry_level) {
-                    true => {
-                        monkeys[monkey_id].true_monkey
-                    },
-                    false => {
-                        monkeys[monkey_id].false_monkey
-                    },
-                };
+// None when the result doesn't fit into i64
+fn lcm(a: i64, b: i64) -> Option<i64> {
+    (a / gcd(a, b)).checked_mul(b)
+}
 
-                match target_monkey {
-                    Some(id) => {
-                        match is_first {
-                            true => monkeys[id].add_item1(worry_level),
-                            false => monkeys[id].add_item2(worry_level),
-                        };
-                    },
-                    None => return Err(String::from("No target monkey")),
+fn top_two<T: Copy + Ord + Default>(counts: &[T]) -> (T, T) {
+    let mut top1 = T::default();
+    let mut top2 = T::default();
+    for count in counts {
+        let count = *count;
+        if count > top1 {
+            top2 = top1;
+            top1 = count;
+            continue;
+        }
+        if count > top2 {
+            top2 = count;
+        }
+    }
+    (top1, top2)
+}
+
+fn monkey_business(counts: &[usize]) -> usize {
+    let (top1, top2) = top_two(counts);
+    top1 * top2
+}
+
+// Worry levels and inspection counts of single simulation run. Worry levels are either kept whole
+// (works for any relief factor and operation, but gets slow as the numbers grow) or reduced modulo
+// given number, which is only valid without relief.
+struct Simulation<'a, N: Number> {
+    monkeys: &'a [MonkeyDef],
+    relief: N,
+    modulus: Option<N>,
+    items: Vec<Vec<N>>,
+    inspected: Vec<usize>,
+}
+
+impl<'a, N: Number> Simulation<'a, N> {
+    fn new(monkeys: &'a [MonkeyDef], relief: i64, modulus: Option<i64>) -> Result<Self, String> {
+        if relief < 1 {
+            return Err(format!("Invalid relief factor {relief}"));
+        }
+        // Dividing the reduced worry level doesn't give the reduced divided worry level
+        if relief != 1 && modulus.is_some() {
+            return Err(String::from("Worry levels can't be reduced when relief factor is used"));
+        }
+        Ok(Self {
+            monkeys,
+            relief: N::from_i64(relief),
+            modulus: modulus.map(N::from_i64),
+            items: monkeys.iter().map(|monkey| monkey.items.iter().map(|item| N::from_i64(*item)).collect()).collect(),
+            inspected: vec![0; monkeys.len()],
+        })
+    }
+
+    fn round(&mut self) -> Result<(), String> {
+        for monkey in self.monkeys {
+            let divisible_by = N::from_i64(monkey.divisible_by);
+            for item in std::mem::take(&mut self.items[monkey.id]) {
+                self.inspected[monkey.id] += 1;
+                let mut worry_level = monkey.operation.eval(&item)?.div_euclid(&self.relief)?;
+                if let Some(modulus) = &self.modulus {
+                    worry_level = worry_level.rem_euclid(modulus)?;
                 }
+                let target_monkey = match worry_level.rem_euclid(&divisible_by)?.is_zero() {
+                    true => monkey.true_monkey,
+                    false => monkey.false_monkey,
+                };
+                self.items[target_monkey].push(worry_level);
             }
         }
+        Ok(())
     }
 
-    let mut top1 = 0;
-    let mut top2 = 0;
+    fn run(&mut self, rounds: usize) -> Result<usize, String> {
+        for _round in 0..rounds {
+            self.round()?;
+        }
+        Ok(monkey_business(&self.inspected))
+    }
+}
+
+// Least common multiple of all the divisible_by values (and constants used with % in operations),
+// None if some operation can't be computed from reduced worry level or the multiple is too big
+fn modulus(monkeys: &[MonkeyDef]) -> Option<i64> {
+    let mut max_divisible = 1;
     for monkey in monkeys {
-        let count = match is_first {
-            true => monkey.inspected_count1,
-            false => monkey.inspected_count2,
+        max_divisible = lcm(max_divisible, monkey.divisible_by)?;
+        for constant in monkey.operation.modular_constants()? {
+            max_divisible = lcm(max_divisible, constant)?;
+        }
+    }
+    Some(max_divisible)
+}
+
+// Monkey business for every relief factor, each simulated in its own thread
+fn sweep(monkeys: &[MonkeyDef], reliefs: &[i64], rounds: usize) -> Result<Vec<(i64, usize)>, String> {
+    let modulus = modulus(monkeys);
+    std::thread::scope(|scope| {
+        let handles = reliefs.iter().map(|relief| scope.spawn(move || {
+            let result = match (relief, modulus) {
+                (1, Some(_)) => Simulation::<i64>::new(monkeys, 1, modulus)?.run(rounds),
+                _ => Simulation::<BigInt>::new(monkeys, *relief, None)?.run(rounds),
+            };
+            result.map(|business| (*relief, business))
+        })).collect::<Vec<_>>();
+        handles.into_iter().map(|handle| match handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(String::from("Simulation thread panicked")),
+        }).collect()
+    })
+}
+
+pub fn run_sweep<R: Read>(input: BufReader<R>, rounds: usize) -> Result<String, String> {
+    let monkeys = parse(input)?;
+    let reliefs = (1..=10).collect::<Vec<i64>>();
+    Ok(sweep(&monkeys, &reliefs, rounds)?.iter()
+        .map(|(relief, business)| format!("Relief {relief}: monkey business {business}\n"))
+        .collect())
+}
+
+type ItemState = (usize, i64); // Monkey holding the item and its worry level
+
+// Follows single item through one round, inspected monkeys are added to counts
+fn item_round(monkeys: &[MonkeyDef], state: ItemState, max_divisible: i64, counts: &mut [u128]) -> Result<ItemState, String> {
+    let (mut monkey_id, mut worry) = state;
+    loop {
+        let monkey = &monkeys[monkey_id];
+        counts[monkey_id] += 1;
+        worry = monkey.operation.eval(&worry)?.rem_euclid(max_divisible);
+        let target_monkey = match worry % monkey.divisible_by == 0 {
+            true => monkey.true_monkey,
+            false => monkey.false_monkey,
         };
-        if count > top1 {
-            top2 = top1;
-            top1 = count;
-            continue;
+        // Monkeys with lower ID already had their turn in this round
+        let same_round = target_monkey > monkey_id;
+        monkey_id = target_monkey;
+        if !same_round {
+            return Ok((monkey_id, worry));
         }
-        if count > top2 {
-            top2 = count;
+    }
+}
+
+fn item_rounds(monkeys: &[MonkeyDef], mut state: ItemState, rounds: u64, max_divisible: i64, counts: &mut [u128]) -> Result<ItemState, String> {
+    for _ in 0..rounds {
+        state = item_round(monkeys, state, max_divisible, counts)?;
+    }
+    Ok(state)
+}
+
+// Brent's cycle detection giving up after the given number of rounds, returns number of rounds
+// before the item starts looping and the length of the loop
+fn find_cycle(monkeys: &[MonkeyDef], start: ItemState, max_divisible: i64, limit: u64) -> Result<Option<(u64, u64)>, String> {
+    let mut ignored = vec![0u128; monkeys.len()];
+    let mut power = 1;
+    let mut cycle_len = 1;
+    let mut steps = 1;
+    let mut tortoise = start;
+    let mut hare = item_round(monkeys, start, max_divisible, &mut ignored)?;
+    while tortoise != hare {
+        if steps >= limit {
+            return Ok(None);
+        }
+        if power == cycle_len {
+            tortoise = hare;
+            power *= 2;
+            cycle_len = 0;
         }
+        hare = item_round(monkeys, hare, max_divisible, &mut ignored)?;
+        cycle_len += 1;
+        steps += 1;
     }
-    return Ok(top1 * top2);
+
+    let mut tortoise = start;
+    let mut hare = item_rounds(monkeys, start, cycle_len, max_divisible, &mut ignored)?;
+    let mut cycle_start = 0;
+    while tortoise != hare {
+        tortoise = item_round(monkeys, tortoise, max_divisible, &mut ignored)?;
+        hare = item_round(monkeys, hare, max_divisible, &mut ignored)?;
+        cycle_start += 1;
+    }
+    Ok(Some((cycle_start, cycle_len)))
 }
 
-pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
-    let mut monkeys = match parse(input) {
-        Ok(m) => m,
-        Err(e) => return Err(e),
-    };
+// Items never interact with each other, so each one can be followed on its own. With worry levels
+// reduced modulo max_divisible, the item has finite number of states and eventually loops, so only
+// the rounds before the loop and single loop iteration need to be simulated. The loop is only
+// looked for within the simulated rounds, as there may be too many states to find it at all.
+fn fast_forward(monkeys: &[MonkeyDef], rounds: u64, max_divisible: i64) -> Result<Vec<u128>, String> {
+    let mut counts = vec![0u128; monkeys.len()];
 
-    // In second part, we would get out of i64 range, but given the actual worry level value is not used
-    // we can just store reminder for all possible divisible_by values - mutliply all the divisible_by values
-    let mut max_divisble = 1;
-    for monkey in &monkeys {
-        match monkey.divisible_by {
-            Some(div) => {
-                max_divisble *= div;
-            },
-            None => return Err(String::from("No divisible_by")),
+    for monkey in monkeys {
+        for item in monkey.items.iter() {
+            let start = (monkey.id, (*item).rem_euclid(max_divisible));
+            let (cycle_start, cycle_len) = match find_cycle(monkeys, start, max_divisible, rounds)? {
+                Some((cycle_start, cycle_len)) if cycle_start + cycle_len < rounds => (cycle_start, cycle_len),
+                _ => {
+                    item_rounds(monkeys, start, rounds, max_divisible, &mut counts)?;
+                    continue;
+                },
+            };
+
+            let looping = item_rounds(monkeys, start, cycle_start, max_divisible, &mut counts)?;
+            let mut cycle_counts = vec![0u128; monkeys.len()];
+            item_rounds(monkeys, looping, cycle_len, max_divisible, &mut cycle_counts)?;
+            let remaining = rounds - cycle_start;
+            for (count, cycle_count) in counts.iter_mut().zip(cycle_counts) {
+                *count += cycle_count * (remaining / cycle_len) as u128;
+            }
+            item_rounds(monkeys, looping, remaining % cycle_len, max_divisible, &mut counts)?;
         }
     }
+    Ok(counts)
+}
 
-    let result1 = match run_for(&mut monkeys, true, 20, 3, max_divisble) {
-        Ok(r) => r,
-        Err(e) => return Err(e),
-    };
-    let result2 = match run_for(&mut monkeys, false, 10000, 1, max_divisble) {
-        Ok(r) => r,
-        Err(e) => return Err(e),
+// Problems with the monkeys worth reporting, whichever way they are simulated
+pub fn check<R: Read>(input: BufReader<R>) -> Result<Vec<String>, String> {
+    Ok(warnings(&parse(input)?))
+}
+
+pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
+    run_with_rounds(input, 10000)
+}
+
+pub fn run_with_rounds<R: Read>(input: BufReader<R>, rounds: u64) -> Result<(String, String), String> {
+    let monkeys = parse(input)?;
+
+    // Reducing the worry level isn't valid when it's divided afterwards, so first part uses whole numbers
+    let result1 = Simulation::<BigInt>::new(&monkeys, 3, None)?.run(20)?;
+
+    // In second part, we would get out of i64 range, but given the actual worry level value is not used
+    // we can just store reminder for all possible divisible_by values
+    let result2 = match modulus(&monkeys) {
+        Some(max) => {
+            let (top1, top2) = top_two(&fast_forward(&monkeys, rounds, max)?);
+            BigInt::from(top1).mul(&BigInt::from(top2))?.to_string()
+        },
+        // Operations with division lose information when reduced, so the worry levels must be kept whole
+        None => match usize::try_from(rounds) {
+            Ok(rounds) => Simulation::<i64>::new(&monkeys, 1, None)?.run(rounds)?.to_string(),
+            Err(_) => return Err(format!("Unable to simulate {rounds} rounds")),
+        },
     };
 
     Ok((
         format!("{}", result1),
-        format!("{}", result2),
+        result2,
     ))
 }
 
@@ -290,9 +441,7 @@ mod test {
     use super::*;
     use crate::test_util::str_to_buf_reader;
 
-    #[test]
-    fn test_d11_01() {
-        let input = String::from("Monkey 0:
+    pub const SAMPLE: &str = "Monkey 0:
   Starting items: 79, 98
   Operation: new = old * 19
   Test: divisible by 23
@@ -318,7 +467,11 @@ Monkey 3:
   Operation: new = old + 3
   Test: divisible by 17
     If true: throw to monkey 0
-    If false: throw to monkey 1");
+    If false: throw to monkey 1";
+
+    #[test]
+    fn test_d11_01() {
+        let input = String::from(SAMPLE);
         let result = run(str_to_buf_reader(&input)).expect("Run failed");
         assert_eq!(
             result.0,
@@ -330,6 +483,117 @@ Monkey 3:
         );
     }
 
+    #[test]
+    fn test_d11_expressions() {
+        let input = SAMPLE
+            .replace("old * 19", "(old + 2) * 5")
+            .replace("old + 6", "old * 3 + old % 10")
+            .replace("old * old", "old - 1");
+        // Reduced worry levels must give the same result as the whole ones
+        let monkeys = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let max = 23 * 19 * 13 * 17 * 10;
+        assert_eq!(modulus(&monkeys), Some(max));
+        let mut reduced = Simulation::<i64>::new(&monkeys, 1, Some(max)).unwrap();
+        let mut whole = Simulation::<i64>::new(&monkeys, 1, None).unwrap();
+        assert_eq!(reduced.run(12), whole.run(12));
+        assert!(whole.items.iter().any(|items| items.iter().any(|item| *item > max)));
+        run(str_to_buf_reader(&input)).expect("Run failed");
+
+        let input = input.replace("old - 1", "old / 2");
+        let monkeys = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        assert_eq!(modulus(&monkeys), None);
+        assert!(Simulation::<i64>::new(&monkeys, 1, None).unwrap().run(10000).is_err());
+        assert!(run(str_to_buf_reader(&input)).is_err());
+
+        // Modulo by zero can't be reduced, whole numbers fail on it
+        let input = SAMPLE.replace("old + 3", "old % 0");
+        let monkeys = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        assert_eq!(modulus(&monkeys), None);
+        assert!(run(str_to_buf_reader(&input)).is_err());
+
+        // Least common multiple out of i64 range
+        let input = SAMPLE
+            .replace("by 23", "by 1000003")
+            .replace("by 19", "by 1000033")
+            .replace("by 13", "by 1000037")
+            .replace("by 17", "by 1000039");
+        let monkeys = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        assert_eq!(lcm(1000003 * 1000033, 1000037 * 1000039), None);
+        assert_eq!(modulus(&monkeys), None);
+        assert!(run(str_to_buf_reader(&input)).is_err());
+    }
+
+    #[test]
+    fn test_d11_exact() {
+        let load = || {
+            let f = std::fs::File::open("src/d11/input.txt").expect("No src/11/input.txt file");
+            parse(BufReader::new(f)).expect("Parse failed")
+        };
+        let monkeys = load();
+        let max = monkeys.iter().map(|m| m.divisible_by).product::<i64>();
+
+        // Modular shortcut matches whole numbers without relief
+        for rounds in [1, 10, 20] {
+            let exact = Simulation::<BigInt>::new(&monkeys, 1, None).unwrap().run(rounds);
+            let reduced = Simulation::<i64>::new(&monkeys, 1, Some(max)).unwrap().run(rounds);
+            assert_eq!(reduced, exact);
+        }
+
+        // With relief the reduced numbers would get divided, which gives wrong results
+        assert!(Simulation::<i64>::new(&monkeys, 2, Some(max)).is_err());
+        assert!(Simulation::<i64>::new(&monkeys, 0, None).is_err());
+    }
+
+    #[test]
+    fn test_d11_sweep() {
+        let f = std::fs::File::open("src/d11/input.txt").expect("No src/11/input.txt file");
+        let monkeys = parse(BufReader::new(f)).expect("Parse failed");
+        let reliefs = (1..=10).collect::<Vec<i64>>();
+        let results = sweep(&monkeys, &reliefs, 20).expect("Sweep failed");
+        assert_eq!(results.iter().map(|(relief, _)| *relief).collect::<Vec<i64>>(), reliefs);
+        for (relief, business) in results {
+            let expected = Simulation::<BigInt>::new(&monkeys, relief, None).unwrap().run(20).unwrap();
+            assert_eq!(business, expected, "relief {relief}");
+        }
+        assert_eq!(sweep(&monkeys, &[3], 20), Ok(vec![(3, 78678)]));
+    }
+
+    #[test]
+    fn test_d11_fast_forward() {
+        let load = || {
+            let f = std::fs::File::open("src/d11/input.txt").expect("No src/11/input.txt file");
+            parse(BufReader::new(f)).expect("Parse failed")
+        };
+        let monkeys = load();
+        let max = monkeys.iter().map(|m| m.divisible_by).product::<i64>();
+
+        for rounds in [0, 1, 7, 20, 123, 1000, 4321] {
+            let mut simulated = Simulation::<i64>::new(&monkeys, 1, Some(max)).unwrap();
+            simulated.run(rounds).expect("Run failed");
+            let counts = fast_forward(&monkeys, rounds as u64, max).expect("Fast forward failed");
+            assert_eq!(counts, simulated.inspected.iter().map(|count| *count as u128).collect::<Vec<u128>>(), "{rounds} rounds");
+        }
+
+        let counts = fast_forward(&monkeys, u64::MAX, max).expect("Fast forward failed");
+        assert!(counts.iter().all(|count| *count > u64::MAX as u128));
+
+        // Too many states to find a loop within the rounds, so the rounds are just simulated
+        let input = SAMPLE
+            .replace("old * 19", "old + 19")
+            .replace("old * old", "old + 5")
+            .replace("by 23", "by 100003")
+            .replace("by 19", "by 100019")
+            .replace("by 13", "by 100043")
+            .replace("by 17", "by 7");
+        let monkeys = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let max = modulus(&monkeys).expect("No modulus");
+        assert!(max > 1_000_000_000_000_000);
+        let counts = fast_forward(&monkeys, 10000, max).expect("Fast forward failed");
+        let mut simulated = Simulation::<i64>::new(&monkeys, 1, Some(max)).unwrap();
+        simulated.run(10000).expect("Run failed");
+        assert_eq!(counts, simulated.inspected.iter().map(|count| *count as u128).collect::<Vec<u128>>());
+    }
+
     #[test]
     fn test_d11_final() {
         let f = std::fs::File::open("src/d11/input.txt").expect("No src/11/input.txt file");
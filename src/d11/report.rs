@@ -0,0 +1,129 @@
+use std::io::{Read, BufReader};
+
+use super::{monkey_business, parse, Simulation};
+use super::bigint::BigInt;
+
+struct RoundReport {
+    items: Vec<Vec<BigInt>>,
+    inspected: Vec<usize>,
+}
+
+struct Report {
+    rounds: Vec<RoundReport>,
+}
+
+fn join(items: &[BigInt], separator: &str) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(separator)
+}
+
+impl Report {
+    fn new<R: Read>(input: BufReader<R>, rounds: usize, relief: i64) -> Result<Self, String> {
+        let monkeys = parse(input)?;
+        let mut simulation = Simulation::<BigInt>::new(&monkeys, relief, None)?;
+        let mut report = Self { rounds: Vec::new() };
+        for _round in 0..rounds {
+            simulation.round()?;
+            report.rounds.push(RoundReport {
+                items: simulation.items.clone(),
+                inspected: simulation.inspected.clone(),
+            });
+        }
+        Ok(report)
+    }
+
+    // Same format as used in the puzzle description
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (round, report) in self.rounds.iter().enumerate() {
+            text.push_str(&format!("== After round {} ==\n", round + 1));
+            for (monkey_id, items) in report.items.iter().enumerate() {
+                text.push_str(format!("Monkey {monkey_id}: {}", join(items, ", ")).trim_end());
+                text.push('\n');
+            }
+            for (monkey_id, count) in report.inspected.iter().enumerate() {
+                text.push_str(&format!("Monkey {monkey_id} inspected items {count} times.\n"));
+            }
+            text.push('\n');
+        }
+        if let Some(last) = self.rounds.last() {
+            text.push_str(&format!("Monkey business: {}\n", monkey_business(&last.inspected)));
+        }
+        text
+    }
+
+    // One line per monkey and round, items are separated by spaces
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("round,monkey,inspected,item_count,items\n");
+        for (round, report) in self.rounds.iter().enumerate() {
+            for (monkey_id, items) in report.items.iter().enumerate() {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    round + 1,
+                    monkey_id,
+                    report.inspected[monkey_id],
+                    items.len(),
+                    join(items, " "),
+                ));
+            }
+        }
+        csv
+    }
+}
+
+pub fn report_text<R: Read>(input: BufReader<R>, rounds: usize, relief: i64) -> Result<String, String> {
+    Ok(Report::new(input, rounds, relief)?.to_text())
+}
+
+pub fn report_csv<R: Read>(input: BufReader<R>, rounds: usize, relief: i64) -> Result<String, String> {
+    Ok(Report::new(input, rounds, relief)?.to_csv())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::str_to_buf_reader;
+    use super::super::test::SAMPLE;
+
+    #[test]
+    fn test_d11_report() {
+        let input = String::from(SAMPLE);
+        let text = report_text(str_to_buf_reader(&input), 20, 3).expect("Report failed");
+        assert!(text.starts_with("== After round 1 ==
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2:
+Monkey 3:
+Monkey 0 inspected items 2 times.
+Monkey 1 inspected items 4 times.
+Monkey 2 inspected items 3 times.
+Monkey 3 inspected items 5 times.
+
+== After round 2 ==
+Monkey 0: 695, 10, 71, 135, 350
+"), "{text}");
+        assert!(text.ends_with("== After round 20 ==
+Monkey 0: 10, 12, 14, 26, 34
+Monkey 1: 245, 93, 53, 199, 115
+Monkey 2:
+Monkey 3:
+Monkey 0 inspected items 101 times.
+Monkey 1 inspected items 95 times.
+Monkey 2 inspected items 7 times.
+Monkey 3 inspected items 105 times.
+
+Monkey business: 10605
+"), "{text}");
+
+        let csv = report_csv(str_to_buf_reader(&input), 2, 3).expect("Report failed");
+        assert_eq!(csv, "round,monkey,inspected,item_count,items
+1,0,2,4,20 23 27 26
+1,1,4,6,2080 25 167 207 401 1046
+1,2,3,0,
+1,3,5,0,
+2,0,6,5,695 10 71 135 350
+2,1,10,5,43 49 58 55 362
+2,2,4,0,
+2,3,10,0,
+");
+    }
+}
@@ -0,0 +1,94 @@
+use super::MonkeyDef;
+
+// Checks that monkeys are defined in order and only throw to other existing monkeys
+pub fn validate(monkeys: &[MonkeyDef]) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for (index, monkey) in monkeys.iter().enumerate() {
+        let id = monkey.id;
+        if id != index {
+            errors.push(format!("Monkey {id} defined at position {index}, expected monkey {index}"));
+        }
+        if monkey.divisible_by <= 0 {
+            errors.push(format!("Monkey {id} tests divisibility by {}", monkey.divisible_by));
+        }
+        for (branch, target) in [("true", monkey.true_monkey), ("false", monkey.false_monkey)] {
+            if target >= monkeys.len() {
+                errors.push(format!("Monkey {id} throws to nonexistent monkey {target} if {branch}"));
+            } else if target == id {
+                errors.push(format!("Monkey {id} throws to itself if {branch}"));
+            }
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("\n")),
+    }
+}
+
+// Monkeys that never get any item, neither at the start nor thrown by other monkeys
+pub fn unreachable_monkeys(monkeys: &[MonkeyDef]) -> Vec<usize> {
+    let mut reached = monkeys.iter().map(|monkey| !monkey.items.is_empty()).collect::<Vec<bool>>();
+    let mut queue = (0..monkeys.len()).filter(|id| reached[*id]).collect::<Vec<usize>>();
+    while let Some(id) = queue.pop() {
+        for target in [monkeys[id].true_monkey, monkeys[id].false_monkey] {
+            if target < monkeys.len() && !reached[target] {
+                reached[target] = true;
+                queue.push(target);
+            }
+        }
+    }
+    (0..monkeys.len()).filter(|id| !reached[*id]).collect()
+}
+
+// Problems which don't prevent running the simulation
+pub fn warnings(monkeys: &[MonkeyDef]) -> Vec<String> {
+    let unreachable = unreachable_monkeys(monkeys);
+    match unreachable.is_empty() {
+        true => Vec::new(),
+        false => vec![format!("Monkeys {unreachable:?} never get any item")],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse;
+    use super::*;
+    use crate::test_util::str_to_buf_reader;
+
+    fn monkey(id: usize, items: &str, true_monkey: usize, false_monkey: usize) -> String {
+        format!("Monkey {id}:
+  Starting items: {items}
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey {true_monkey}
+    If false: throw to monkey {false_monkey}
+")
+    }
+
+    #[test]
+    fn test_d11_validate() {
+        let parse_str = |input: String| parse(str_to_buf_reader(&input));
+
+        let monkeys = parse_str([monkey(0, "1, 2", 1, 2), monkey(1, "", 0, 2), monkey(2, "", 0, 1), monkey(3, "", 0, 1)].join("\n")).expect("Parse failed");
+        assert_eq!(unreachable_monkeys(&monkeys), vec![3]);
+        assert_eq!(warnings(&monkeys), vec![String::from("Monkeys [3] never get any item")]);
+        assert_eq!(warnings(&monkeys[..3]), Vec::<String>::new());
+
+        let err = parse_str([monkey(1, "1", 1, 0), monkey(0, "", 1, 1)].join("\n")).unwrap_err();
+        assert_eq!(err, "Monkey 1 defined at position 0, expected monkey 0
+Monkey 1 throws to itself if true
+Monkey 0 defined at position 1, expected monkey 1");
+
+        let err = parse_str([monkey(0, "1", 1, 5), monkey(1, "", 0, 0)].join("\n")).unwrap_err();
+        assert_eq!(err, "Monkey 0 throws to nonexistent monkey 5 if false");
+
+        let err = parse_str(monkey(0, "1", 1, 2).replace("    If false: throw to monkey 2\n", "") + &monkey(1, "", 0, 0)).unwrap_err();
+        assert_eq!(err, "Monkey 0 is missing: If false");
+
+        let err = parse_str(monkey(0, "1", 1, 1).replace("  Test: divisible by 23\n", "")).unwrap_err();
+        assert_eq!(err, "Monkey 0 is missing: Test");
+
+        let err = parse_str(monkey(0, "1", 1, 1).replace("Monkey 0:\n", "")).unwrap_err();
+        assert_eq!(err, "Line outside of monkey definition:   Starting items: 1");
+    }
+}
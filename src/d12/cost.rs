@@ -0,0 +1,140 @@
+use std::{fs::File, io::{Read, BufReader, BufRead}};
+
+// Price of a single step between cells of given heights (0 for 'a' to 25 for 'z')
+pub trait Cost {
+    fn step(&self, from: u8, to: u8) -> usize;
+
+    // No step is cheaper than this, keeps the A* estimate a lower bound
+    fn min_step(&self) -> usize;
+}
+
+// Every step costs the same, as in the puzzle
+pub struct Unit;
+
+impl Cost for Unit {
+    fn step(&self, _from: u8, _to: u8) -> usize {
+        1
+    }
+
+    fn min_step(&self) -> usize {
+        1
+    }
+}
+
+// Every level climbed costs extra
+struct Uphill(usize);
+
+impl Cost for Uphill {
+    fn step(&self, from: u8, to: u8) -> usize {
+        1 + self.0 * to.saturating_sub(from) as usize
+    }
+
+    fn min_step(&self) -> usize {
+        1
+    }
+}
+
+// Every level descended costs extra
+struct Descent(usize);
+
+impl Cost for Descent {
+    fn step(&self, from: u8, to: u8) -> usize {
+        1 + self.0 * from.saturating_sub(to) as usize
+    }
+
+    fn min_step(&self) -> usize {
+        1
+    }
+}
+
+// Entering a cell costs the price of its letter
+struct Terrain([usize; 26]);
+
+impl Terrain {
+    // Lines with letter and its cost, e.g. `c 5`, letters not listed cost 1
+    fn parse<R: Read>(input: BufReader<R>) -> Result<Self, String> {
+        let mut costs = [1; 26];
+        for (number, line) in input.lines().enumerate() {
+            let line = match line {
+                Ok(ln) => ln,
+                Err(e) => return Err(format!("Unable to read line: {e}")),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (letter, cost) = match line.split_once(char::is_whitespace) {
+                Some((letter, cost)) => (letter, cost.trim()),
+                None => return Err(format!("Line {}: expected letter and cost", number + 1)),
+            };
+            let height = match letter.as_bytes() {
+                [ch @ b'a'..=b'z'] => ch - b'a',
+                _ => return Err(format!("Line {}: invalid letter '{letter}'", number + 1)),
+            };
+            costs[height as usize] = match cost.parse::<usize>() {
+                Ok(cost) => cost,
+                Err(e) => return Err(format!("Line {}: invalid cost '{cost}': {e}", number + 1)),
+            };
+        }
+        Ok(Self(costs))
+    }
+}
+
+impl Cost for Terrain {
+    fn step(&self, _from: u8, to: u8) -> usize {
+        self.0[to as usize]
+    }
+
+    fn min_step(&self) -> usize {
+        self.0.iter().copied().min().unwrap_or(0)
+    }
+}
+
+// Cost model from its description: `unit`, `uphill:N`, `descent:N` or `terrain:FILE`
+pub fn cost_model(spec: &str) -> Result<Box<dyn Cost>, String> {
+    let (name, param) = match spec.split_once(':') {
+        Some((name, param)) => (name, Some(param)),
+        None => (spec, None),
+    };
+    let factor = || match param.map(|param| param.parse::<usize>()) {
+        Some(Ok(factor)) => Ok(factor),
+        Some(Err(e)) => Err(format!("Invalid {name} factor: {e}")),
+        None => Err(format!("Missing {name} factor")),
+    };
+    match name {
+        "unit" => Ok(Box::new(Unit)),
+        "uphill" => Ok(Box::new(Uphill(factor()?))),
+        "descent" => Ok(Box::new(Descent(factor()?))),
+        "terrain" => match param.map(File::open) {
+            Some(Ok(f)) => Ok(Box::new(Terrain::parse(BufReader::new(f))?)),
+            Some(Err(e)) => Err(format!("Unable to open terrain file: {e}")),
+            None => Err(String::from("Missing terrain file")),
+        },
+        _ => Err(format!("Unknown cost model {name}")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::str_to_buf_reader;
+
+    #[test]
+    fn test_d12_cost() {
+        let terrain = Terrain::parse(str_to_buf_reader(&String::from("b 10\n\nz 0\n"))).expect("Parse failed");
+        assert_eq!((terrain.step(0, 1), terrain.step(1, 0), terrain.step(0, 25)), (10, 1, 0));
+        assert_eq!(terrain.min_step(), 0);
+        assert_eq!(Terrain::parse(str_to_buf_reader(&String::from("a 1\nB 2"))).err(), Some(String::from("Line 2: invalid letter 'B'")));
+        assert!(Terrain::parse(str_to_buf_reader(&String::from("a -1"))).is_err());
+        assert!(Terrain::parse(str_to_buf_reader(&String::from("a"))).is_err());
+
+        let uphill = cost_model("uphill:3").expect("Invalid model");
+        assert_eq!((uphill.step(2, 5), uphill.step(5, 2)), (10, 1));
+        let descent = cost_model("descent:3").expect("Invalid model");
+        assert_eq!((descent.step(2, 5), descent.step(5, 2)), (1, 10));
+        assert_eq!(cost_model("unit").expect("Invalid model").step(0, 1), 1);
+        assert!(cost_model("uphill").is_err());
+        assert!(cost_model("uphill:x").is_err());
+        assert!(cost_model("terrain:missing/file.txt").is_err());
+        assert!(cost_model("flat").is_err());
+    }
+}
@@ -1,4 +1,8 @@
-use std::{io::{Read, BufReader}, collections::{BinaryHeap, HashMap}, cmp::Ordering};
+use std::{io::{Read, BufReader}, collections::{BinaryHeap, HashMap, VecDeque}, cmp::Ordering};
+
+mod cost;
+
+pub use cost::{cost_model, Cost, Unit};
 
 #[derive(Debug)]
 struct Node {
@@ -64,141 +68,309 @@ impl Matrix {
             },
         }
     }
-}
 
-fn dijkstra(matrix: &Matrix, previous: &mut HashMap<usize, usize>, first_level_cost: usize) -> bool {
-    let mut neighbours: Vec<usize> = Vec::new();
-    neighbours.reserve(4);
-    let mut heap: BinaryHeap<Node> = BinaryHeap::new();
-    heap.push(Node { index: matrix.start, distance: 0 });
+    fn rows(&self) -> usize {
+        self.items.len() / self.width
+    }
 
-    loop {
-        let item = heap.pop();
-        match item {
-            Some(node) => {
-                let height = matrix.items[node.index];
+    // Column and row of the cell
+    fn position(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
 
-                if node.index >= matrix.width {
-                    neighbours.push(node.index - matrix.width);
-                }
-                if node.index <= matrix.items.len() - matrix.width {
-                    neighbours.push(node.index + matrix.width);
-                }
-                if node.index % matrix.width > 0 {
-                    neighbours.push(node.index - 1);
-                }
-                if (node.index % matrix.width) < matrix.width {
-                    neighbours.push(node.index + 1);
+    fn neighbours(&self, index: usize, rules: &Rules) -> Vec<usize> {
+        let (x, y) = self.position(index);
+        let mut neighbours: Vec<usize> = Vec::with_capacity(8);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) || (dx != 0 && dy != 0 && !rules.diagonal) {
+                    continue;
                 }
+                let (nx, ny) = match (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+                    (Some(nx), Some(ny)) if nx < self.width && ny < self.rows() => (nx, ny),
+                    _ => continue,
+                };
+                neighbours.push(ny * self.width + nx);
+            }
+        }
+        neighbours
+    }
 
-                for neighbour_index in neighbours.drain(..) {
-                    let neighbour_height = match matrix.items.get(neighbour_index) {
-                        Some(n) => n,
-                        None => {
-                            continue;
-                        },
-                    };
-
-                    if *neighbour_height > height + 1 {
-                        continue;
-                    }
-
-                    // We've been here, go to next neighbour
-                    match previous.get(&neighbour_index) {
-                        Some(_) => {
-                            continue;
-                        },
-                        None => {}
-                    }
-
-                    previous.insert(neighbour_index, node.index);
-                    if neighbour_index == matrix.end {
-                        return true;
-                    }
-
-                    let mut new_distance = node.distance + 1;
-                    if height == 0 && *neighbour_height == 0 {
-                        new_distance = node.distance + first_level_cost;
-                    }
-
-                    heap.push(Node { index: neighbour_index, distance: new_distance });
-                }
-            },
-            None => return false,
+    fn can_climb(&self, from: usize, to: usize, rules: &Rules) -> bool {
+        let (from, to) = (self.items[from], self.items[to]);
+        match to >= from {
+            true => to - from <= rules.max_climb,
+            false => rules.max_descent.is_none_or(|max_descent| from - to <= max_descent),
+        }
+    }
+}
+
+// Allowed moves between cells
+#[derive(Debug, Clone, Copy)]
+struct Rules {
+    // Move also to the 4 diagonal neighbours
+    diagonal: bool,
+    max_climb: u8,
+    // None when descent is unlimited
+    max_descent: Option<u8>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            diagonal: false,
+            max_climb: 1,
+            max_descent: None,
         }
     }
 }
 
-fn _draw_result(matrix: &Matrix, previous: &HashMap<usize, usize>) {
-    for (index, height) in matrix.items.iter().enumerate() {
-        match previous.get(&index) {
-            Some(_) => print!("{}", (height + b'A') as char),
-            None => print!("{}", (height + b'a') as char),
+// Lower bound of cost left: every step moves by one cell, climbs at most max_climb levels
+// and costs at least the cheapest step
+fn heuristic(matrix: &Matrix, rules: &Rules, cost: &dyn Cost, index: usize, end: usize) -> usize {
+    let (x, y) = matrix.position(index);
+    let (end_x, end_y) = matrix.position(end);
+    let distance = match rules.diagonal {
+        true => x.abs_diff(end_x).max(y.abs_diff(end_y)),
+        false => x.abs_diff(end_x) + y.abs_diff(end_y),
+    };
+    let climb = matrix.items[end].saturating_sub(matrix.items[index]) as usize;
+    distance.max(climb.div_ceil(rules.max_climb.max(1) as usize)) * cost.min_step()
+}
+
+// Cheapest path from start to end, both included
+fn astar(matrix: &Matrix, rules: &Rules, cost: &dyn Cost, start: usize, end: usize) -> Option<Vec<usize>> {
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut costs = vec![usize::MAX; matrix.items.len()];
+    let mut heap: BinaryHeap<Node> = BinaryHeap::new();
+    costs[start] = 0;
+    heap.push(Node { index: start, distance: heuristic(matrix, rules, cost, start, end) });
+
+    while let Some(node) = heap.pop() {
+        if node.index == end {
+            return Some(path(&previous, end));
         }
-        if index % matrix.width == matrix.width - 1 {
-            println!();
+        // Already reached with lower cost
+        if node.distance > costs[node.index] + heuristic(matrix, rules, cost, node.index, end) {
+            continue;
+        }
+        for neighbour_index in matrix.neighbours(node.index, rules) {
+            if !matrix.can_climb(node.index, neighbour_index, rules) {
+                continue;
+            }
+            let new_cost = costs[node.index] + cost.step(matrix.items[node.index], matrix.items[neighbour_index]);
+            if new_cost < costs[neighbour_index] {
+                costs[neighbour_index] = new_cost;
+                previous.insert(neighbour_index, node.index);
+                heap.push(Node { index: neighbour_index, distance: new_cost + heuristic(matrix, rules, cost, neighbour_index, end) });
+            }
         }
     }
+    None
 }
 
-fn backtrack<F>(end: usize, previous: &HashMap<usize, usize>, is_finish: F) -> Result<usize, String> where F: Fn(usize) -> bool {
-    let mut current = end;
-    let mut count = 0;
-    while !is_finish(current) {
-        count += 1;
-        current = match previous.get(&current) {
-            Some(c) => *c,
-            None => return Err(format!("Item with index {} not found in previous", current)),
+// Walks backwards from end until it finds the closest cell matching is_start, every step costs
+// the same. Returns path from that cell to end.
+fn reverse_bfs<F>(matrix: &Matrix, rules: &Rules, end: usize, is_start: F) -> Option<Vec<usize>> where F: Fn(usize) -> bool {
+    // Points towards end, so it's "previous" when going back from the found start
+    let mut next: HashMap<usize, usize> = HashMap::new();
+    let mut queue: VecDeque<usize> = VecDeque::from([end]);
+
+    while let Some(index) = queue.pop_front() {
+        if is_start(index) {
+            let mut path = path(&next, index);
+            path.reverse();
+            return Some(path);
+        }
+        for neighbour_index in matrix.neighbours(index, rules) {
+            if neighbour_index == end || next.contains_key(&neighbour_index) || !matrix.can_climb(neighbour_index, index, rules) {
+                continue;
+            }
+            next.insert(neighbour_index, index);
+            queue.push_back(neighbour_index);
         }
     }
-    Ok(count)
+    None
 }
 
-pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    // Steps needed to get from the closest source to the cell
+    FromSources,
+    // Steps needed to get from the cell to the closest source
+    ToSources,
+}
 
-    let mut matrix = Matrix::new();
+// Number of steps for every cell, None for cells that can't be reached
+fn distance_field(matrix: &Matrix, rules: &Rules, sources: &[usize], direction: Direction) -> Vec<Option<usize>> {
+    let mut distances = vec![None; matrix.items.len()];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for source in sources {
+        if distances[*source].is_none() {
+            distances[*source] = Some(0);
+            queue.push_back(*source);
+        }
+    }
 
-    for item in input.bytes() {
-        match item {
-            Ok(ch) => matrix.add_char(ch),
-            Err(e) => return Err(format!("Unable to read: {}", e.to_string())),
+    while let Some(index) = queue.pop_front() {
+        let distance = distances[index].unwrap_or_default();
+        for neighbour_index in matrix.neighbours(index, rules) {
+            let reachable = match direction {
+                Direction::FromSources => matrix.can_climb(index, neighbour_index, rules),
+                Direction::ToSources => matrix.can_climb(neighbour_index, index, rules),
+            };
+            if reachable && distances[neighbour_index].is_none() {
+                distances[neighbour_index] = Some(distance + 1);
+                queue.push_back(neighbour_index);
+            }
         }
     }
+    distances
+}
 
-    let mut previous: HashMap<usize, usize> = HashMap::new();
-    let result1 = dijkstra(&matrix, &mut previous, 1);
+// Right aligned distances, unreachable cells are shown as '.'
+fn draw_distances(matrix: &Matrix, distances: &[Option<usize>]) -> String {
+    let cells = distances.iter().map(|distance| match distance {
+        Some(distance) => distance.to_string(),
+        None => String::from("."),
+    }).collect::<Vec<String>>();
+    let width = cells.iter().map(|cell| cell.len()).max().unwrap_or(0);
+    cells.chunks(matrix.width).map(|row| {
+        row.iter().map(|cell| format!("{cell:>width$}")).collect::<Vec<String>>().join(" ") + "\n"
+    }).collect()
+}
 
-    // _draw_result(&matrix, &previous);
-    // println!();
+// Reachable cells of the lowest level ordered by their distance, then by position
+fn ranked_trailheads(matrix: &Matrix, distances: &[Option<usize>]) -> Vec<(usize, usize)> {
+    let mut trailheads = (0..matrix.items.len())
+        .filter(|index| matrix.items[*index] == 0)
+        .filter_map(|index| distances[index].map(|distance| (index, distance)))
+        .collect::<Vec<(usize, usize)>>();
+    trailheads.sort_by_key(|(index, distance)| (*distance, *index));
+    trailheads
+}
 
-    if !result1 {
-        return Err(String::from("No solution found for part 1"));
+// Distance of every cell to E (or from S or from all the lowest cells) and list of the lowest cells
+// ordered by the distance
+pub fn distance_report<R: Read>(input: BufReader<R>, source: char) -> Result<String, String> {
+    let matrix = parse(input)?;
+    let (sources, direction) = match source {
+        'E' => (vec![matrix.end], Direction::ToSources),
+        'S' => (vec![matrix.start], Direction::FromSources),
+        'a' => ((0..matrix.items.len()).filter(|index| matrix.items[*index] == 0).collect(), Direction::FromSources),
+        _ => return Err(format!("Unknown source '{source}', expected S, E or a")),
+    };
+    let distances = distance_field(&matrix, &Rules::default(), &sources, direction);
+    let mut report = draw_distances(&matrix, &distances);
+    report.push('\n');
+    for (index, distance) in ranked_trailheads(&matrix, &distances) {
+        let (x, y) = matrix.position(index);
+        report.push_str(&format!("{x},{y}: {distance}\n"));
     }
+    Ok(report)
+}
 
-    let count1 = match backtrack(matrix.end, &previous, move |x| x == matrix.start) {
-        Ok(c) => c,
-        Err(e) => return Err(e),
-    };
+// Marks every cell of the path with the direction of the next step, like in the puzzle description
+fn draw_path(matrix: &Matrix, path: &[usize]) -> String {
+    let mut cells = vec![b'.'; matrix.items.len()];
+    for step in path.windows(2) {
+        let (x, y) = matrix.position(step[0]);
+        let (next_x, next_y) = matrix.position(step[1]);
+        cells[step[0]] = match (next_x.cmp(&x), next_y.cmp(&y)) {
+            (Ordering::Greater, Ordering::Equal) => b'>',
+            (Ordering::Less, Ordering::Equal) => b'<',
+            (Ordering::Equal, Ordering::Greater) => b'v',
+            (Ordering::Equal, Ordering::Less) => b'^',
+            // Diagonal moves
+            (Ordering::Greater, Ordering::Less) | (Ordering::Less, Ordering::Greater) => b'/',
+            _ => b'\\',
+        };
+    }
+    if let Some(last) = path.last() {
+        cells[*last] = b'E';
+    }
+    cells.chunks(matrix.width).map(|row| format!("{}\n", String::from_utf8_lossy(row))).collect()
+}
 
-    // PART TWO
-    previous.drain();
+// One line per step with column and row of the cell and total cost so far, path starts at step 0
+fn path_coordinates(matrix: &Matrix, cost: &dyn Cost, path: &[usize]) -> String {
+    let mut csv = String::from("step,x,y,cost\n");
+    let mut total = 0;
+    for (step, index) in path.iter().enumerate() {
+        if step > 0 {
+            total += cost.step(matrix.items[path[step - 1]], matrix.items[*index]);
+        }
+        let (x, y) = matrix.position(*index);
+        csv.push_str(&format!("{step},{x},{y},{total}\n"));
+    }
+    csv
+}
 
-    let result2 = dijkstra(&matrix, &mut previous, 0);
+fn cheapest_path<R: Read>(input: BufReader<R>, cost: &dyn Cost) -> Result<(Matrix, Vec<usize>), String> {
+    let matrix = parse(input)?;
+    match astar(&matrix, &Rules::default(), cost, matrix.start, matrix.end) {
+        Some(path) => Ok((matrix, path)),
+        None => Err(String::from("No path from S to E")),
+    }
+}
 
-    // _draw_result(&matrix, &previous);
+pub fn path_map<R: Read>(input: BufReader<R>, cost: &dyn Cost) -> Result<String, String> {
+    let (matrix, path) = cheapest_path(input, cost)?;
+    Ok(draw_path(&matrix, &path))
+}
 
-    if !result2 {
-        return Err(String::from("No solution found for part 2"));
+pub fn path_csv<R: Read>(input: BufReader<R>, cost: &dyn Cost) -> Result<String, String> {
+    let (matrix, path) = cheapest_path(input, cost)?;
+    Ok(path_coordinates(&matrix, cost, &path))
+}
+
+// Follows previous links back from the last cell, returned path starts with the first one
+fn path(previous: &HashMap<usize, usize>, last: usize) -> Vec<usize> {
+    let mut path = vec![last];
+    while let Some(index) = previous.get(path.last().unwrap_or(&last)) {
+        path.push(*index);
     }
+    path.reverse();
+    path
+}
 
-    let count2 = match backtrack(matrix.end, &previous, |x| matrix.items[x] == 0) {
-        Ok(c) => c,
-        Err(e) => return Err(e),
+fn parse<R: Read>(input: BufReader<R>) -> Result<Matrix, String> {
+    let mut matrix = Matrix::new();
+
+    for item in input.bytes() {
+        match item {
+            Ok(ch) => matrix.add_char(ch),
+            Err(e) => return Err(format!("Unable to read: {}", e)),
+        }
+    }
+    // Single line without newline
+    if matrix.width == 0 {
+        matrix.width = matrix.items.len();
+    }
+    if matrix.items.is_empty() || !matrix.items.len().is_multiple_of(matrix.width) {
+        return Err(String::from("Map must be non-empty rectangle"));
+    }
+    Ok(matrix)
+}
+
+pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
+    let matrix = parse(input)?;
+    let rules = Rules::default();
+
+    let path1 = match astar(&matrix, &rules, &Unit, matrix.start, matrix.end) {
+        Some(path) => path,
+        None => return Err(String::from("No solution found for part 1")),
+    };
+
+    // PART TWO
+    let path2 = match reverse_bfs(&matrix, &rules, matrix.end, |index| matrix.items[index] == 0) {
+        Some(path) => path,
+        None => return Err(String::from("No solution found for part 2")),
     };
 
     Ok((
-        format!("{}", count1),
-        format!("{}", count2),
+        format!("{}", path1.len() - 1),
+        format!("{}", path2.len() - 1),
     ))
 }
 
@@ -207,6 +379,13 @@ mod test {
     use super::*;
     use crate::test_util::str_to_buf_reader;
 
+    const SAMPLE: &str = "Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi
+";
+
     #[test]
     fn test_d12_01() {
         let input = String::from("Sabqponm
@@ -225,6 +404,179 @@ abdefghi");
         );
     }
 
+    fn check_path(matrix: &Matrix, rules: &Rules, path: &[usize]) {
+        for step in path.windows(2) {
+            assert!(matrix.neighbours(step[0], rules).contains(&step[1]), "{step:?}");
+            assert!(matrix.can_climb(step[0], step[1], rules), "{step:?}");
+        }
+    }
+
+    #[test]
+    fn test_d12_search() {
+        let input = String::from(SAMPLE);
+        let matrix = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let rules = Rules::default();
+
+        let path = astar(&matrix, &rules, &Unit, matrix.start, matrix.end).expect("No path");
+        assert_eq!(path.len(), 32);
+        assert_eq!((path[0], path[31]), (matrix.start, matrix.end));
+        check_path(&matrix, &rules, &path);
+
+        // Searching back to S gives path of the same length
+        let reverse = reverse_bfs(&matrix, &rules, matrix.end, |index| index == matrix.start).expect("No path");
+        assert_eq!(reverse.len(), 32);
+        assert_eq!((reverse[0], reverse[31]), (matrix.start, matrix.end));
+        check_path(&matrix, &rules, &reverse);
+
+        let path = reverse_bfs(&matrix, &rules, matrix.end, |index| matrix.items[index] == 0).expect("No path");
+        assert_eq!(path.len(), 30);
+        assert_eq!(matrix.items[path[0]], 0);
+        check_path(&matrix, &rules, &path);
+
+        assert_eq!(astar(&matrix, &rules, &Unit, matrix.end, matrix.end), Some(vec![matrix.end]));
+        // Whole reachable area is searched when nothing matches
+        assert!(reverse_bfs(&matrix, &rules, matrix.end, |_| false).is_none());
+    }
+
+    #[test]
+    fn test_d12_edges() {
+        let rules = Rules::default();
+        let diagonal = Rules { diagonal: true, ..Rules::default() };
+        let matrix = parse(str_to_buf_reader(&String::from("Sab\ncEz\n"))).expect("Parse failed");
+        assert_eq!(matrix.neighbours(0, &rules), vec![1, 3]);
+        // Last cell of first row doesn't continue to the next row
+        assert_eq!(matrix.neighbours(2, &rules), vec![1, 5]);
+        assert_eq!(matrix.neighbours(5, &rules), vec![2, 4]);
+        assert_eq!(matrix.neighbours(4, &rules), vec![1, 3, 5]);
+        assert_eq!(matrix.neighbours(2, &diagonal), vec![1, 4, 5]);
+        assert_eq!(matrix.neighbours(4, &diagonal), vec![0, 1, 2, 3, 5]);
+        assert_eq!(astar(&matrix, &rules, &Unit, matrix.start, 3), None);
+
+        // Path going around the whole border
+        let input = String::from("Sbcdefghijklm
+zzzzzzzzzzzzn
+Eyxwvutsrqpoo");
+        let matrix = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let path = astar(&matrix, &rules, &Unit, matrix.start, matrix.end).expect("No path");
+        assert_eq!(path.len(), 27);
+        check_path(&matrix, &rules, &path);
+        assert_eq!(reverse_bfs(&matrix, &rules, matrix.end, |index| index == matrix.start), Some(path));
+
+        let matrix = parse(str_to_buf_reader(&String::from("SbcdefghijklmnopqrstuvwxyE"))).expect("Parse failed");
+        assert_eq!(astar(&matrix, &rules, &Unit, matrix.start, matrix.end).map(|path| path.len()), Some(26));
+        assert!(parse(str_to_buf_reader(&String::from("Sab\nE"))).is_err());
+    }
+
+    #[test]
+    fn test_d12_rules() {
+        let input = String::from(SAMPLE);
+        let matrix = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let steps = |rules: &Rules| {
+            let path = astar(&matrix, rules, &Unit, matrix.start, matrix.end);
+            if let Some(path) = &path {
+                check_path(&matrix, rules, path);
+                assert_eq!(reverse_bfs(&matrix, rules, matrix.end, |index| index == matrix.start).map(|p| p.len()), Some(path.len()));
+            }
+            path.map(|path| path.len() - 1)
+        };
+        assert_eq!(steps(&Rules::default()), Some(31));
+        let diagonal = steps(&Rules { diagonal: true, ..Rules::default() }).expect("No path");
+        assert!(diagonal < 31);
+        let climb = steps(&Rules { max_climb: 2, ..Rules::default() }).expect("No path");
+        assert!(climb < 31);
+        assert_eq!(steps(&Rules { max_climb: 25, ..Rules::default() }), Some(7));
+        assert_eq!(steps(&Rules { max_climb: 0, ..Rules::default() }), None);
+        // Shortest path never needs to go down
+        assert_eq!(steps(&Rules { max_descent: Some(0), ..Rules::default() }), Some(31));
+    }
+
+    #[test]
+    fn test_d12_path_output() {
+        let input = String::from(SAMPLE);
+        assert_eq!(path_map(str_to_buf_reader(&input), &Unit).expect("No path"), ">>vv<<<<
+..vvv<<^
+..vv>E^^
+..v>>>^^
+..>>>>>^
+");
+
+        let csv = path_csv(str_to_buf_reader(&input), &Unit).expect("No path");
+        assert!(csv.starts_with("step,x,y,cost\n0,0,0,0\n1,1,0,1\n"), "{csv}");
+        assert!(csv.ends_with("31,5,2,31\n"), "{csv}");
+        assert_eq!(csv.lines().count(), 33);
+
+        let matrix = parse(str_to_buf_reader(&String::from("abc\ndef\nghi"))).expect("Parse failed");
+        assert_eq!(draw_path(&matrix, &[0, 4, 8]), "\\..\n.\\.\n..E\n");
+        assert_eq!(draw_path(&matrix, &[6, 4, 2]), "..E\n./.\n/..\n");
+    }
+
+    #[test]
+    fn test_d12_costs() {
+        let matrix = parse(str_to_buf_reader(&String::from("aba\naaa"))).expect("Parse failed");
+        let rules = Rules::default();
+        let cheapest = |spec: &str| {
+            let cost = cost_model(spec).expect("Invalid model");
+            let path = astar(&matrix, &rules, cost.as_ref(), 0, 2).expect("No path");
+            let total = path.windows(2).map(|step| cost.step(matrix.items[step[0]], matrix.items[step[1]])).sum::<usize>();
+            (path, total)
+        };
+        // Over the hill or around it
+        assert_eq!(cheapest("unit"), (vec![0, 1, 2], 2));
+        assert_eq!(cheapest("uphill:0"), (vec![0, 1, 2], 2));
+        assert_eq!(cheapest("uphill:3"), (vec![0, 3, 4, 5, 2], 4));
+        assert_eq!(cheapest("descent:1"), (vec![0, 1, 2], 3));
+        assert_eq!(cheapest("descent:3"), (vec![0, 3, 4, 5, 2], 4));
+
+        let input = String::from(SAMPLE);
+        let uphill = cost_model("uphill:2").expect("Invalid model");
+        let csv = path_csv(str_to_buf_reader(&input), uphill.as_ref()).expect("No path");
+        // Climbing 25 levels costs 50 on top of the steps
+        assert!(csv.ends_with(",5,2,81\n"), "{csv}");
+    }
+
+    #[test]
+    fn test_d12_distances() {
+        let input = String::from(SAMPLE);
+        let matrix = parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let rules = Rules::default();
+        let to_end = distance_field(&matrix, &rules, &[matrix.end], Direction::ToSources);
+        assert_eq!((to_end[matrix.start], to_end[matrix.end]), (Some(31), Some(0)));
+        let from_start = distance_field(&matrix, &rules, &[matrix.start], Direction::FromSources);
+        assert_eq!(from_start[matrix.end], Some(31));
+
+        // Every cell agrees with the path search
+        for (index, distance) in to_end.iter().enumerate() {
+            let path = reverse_bfs(&matrix, &rules, matrix.end, |i| i == index);
+            assert_eq!(*distance, path.map(|path| path.len() - 1), "{index}");
+        }
+
+        let trailheads = ranked_trailheads(&matrix, &to_end);
+        assert_eq!(trailheads, vec![(32, 29), (1, 30), (8, 30), (24, 30), (0, 31), (16, 31)]);
+
+        let matrix = parse(str_to_buf_reader(&String::from("abcdefghijkl\nzzzzzzzzzzza"))).expect("Parse failed");
+        let distances = distance_field(&matrix, &rules, &[0, 11], Direction::FromSources);
+        assert_eq!(draw_distances(&matrix, &distances), "0 1 2 3 4 5 5 4 3 2 1 0
+. . . . . . . . . . . 1
+");
+        let report = distance_report(str_to_buf_reader(&input), 'E').expect("Report failed");
+        assert_eq!(report, "31 30 29 12 13 14 15 16
+30 29 28 11  2  3  4 17
+31 28 27 10  1  0  5 18
+30 27 26  9  8  7  6 19
+29 28 25 24 23 22 21 20
+
+0,4: 29
+1,0: 30
+0,1: 30
+0,3: 30
+0,0: 31
+0,2: 31
+");
+        let report = distance_report(str_to_buf_reader(&input), 'a').expect("Report failed");
+        assert!(report.starts_with(" 0  0  1 17 16 15 14 13\n"), "{report}");
+        assert!(distance_report(str_to_buf_reader(&input), 'x').is_err());
+    }
+
     #[test]
     fn test_d12_final() {
         let f = std::fs::File::open("src/d12/input.txt").expect("No src/12/input.txt file");
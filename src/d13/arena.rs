@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+
+use super::{parse_packet, Item, Packet, PacketSink};
+
+// Lists know where they end, so the elements after them can be found without a closing token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Number(i32),
+    // Index of the first token after the list
+    List { end: usize },
+}
+
+// All packets stored in one flat token vector, every packet is a range of it
+#[derive(Debug, Default)]
+pub struct PacketArena {
+    tokens: Vec<Token>,
+    packets: Vec<usize>,
+    // Lists being parsed, their end is set when they are closed
+    open: Vec<usize>,
+}
+
+impl PacketSink for PacketArena {
+    fn number(&mut self, number: i32) {
+        self.tokens.push(Token::Number(number));
+    }
+
+    fn start_list(&mut self) {
+        self.open.push(self.tokens.len());
+        self.tokens.push(Token::List { end: 0 });
+    }
+
+    fn end_list(&mut self) {
+        if let Some(start) = self.open.pop() {
+            self.tokens[start] = Token::List { end: self.tokens.len() };
+        }
+    }
+}
+
+impl PacketArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns id of the added packet
+    pub fn push(&mut self, packet: &Packet) -> usize {
+        self.packets.push(self.tokens.len());
+        self.flatten(packet);
+        self.packets.len() - 1
+    }
+
+    fn flatten(&mut self, item: &Item) {
+        match item {
+            Item::Number { item } => self.number(*item),
+            Item::Array { items } => {
+                self.start_list();
+                for item in items {
+                    self.flatten(item);
+                }
+                self.end_list();
+            },
+        }
+    }
+
+    // Parses the packet straight into tokens without building the `Item` tree, returns its id
+    pub fn parse(&mut self, text: &str) -> Result<usize, String> {
+        let start = self.tokens.len();
+        if let Err(e) = parse_packet(text, self) {
+            self.tokens.truncate(start);
+            self.open.clear();
+            return Err(e);
+        }
+        self.packets.push(start);
+        Ok(self.packets.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    // Index of the token following the element starting at given index
+    fn next(&self, index: usize) -> usize {
+        match self.tokens[index] {
+            Token::Number(_) => index + 1,
+            Token::List { end } => end,
+        }
+    }
+
+    // List elements, number is its own only element, so it doesn't need to be wrapped into a list
+    fn elements(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let (first, end) = match self.tokens[index] {
+            Token::Number(_) => (index, index + 1),
+            Token::List { end } => (index + 1, end),
+        };
+        let first = Some(first).filter(|first| *first < end);
+        std::iter::successors(first, move |current| Some(self.next(*current)).filter(|next| *next < end))
+    }
+
+    fn cmp_tokens(&self, a: usize, b: usize) -> Ordering {
+        if let (Token::Number(a), Token::Number(b)) = (self.tokens[a], self.tokens[b]) {
+            return a.cmp(&b);
+        }
+        let (mut a_elements, mut b_elements) = (self.elements(a), self.elements(b));
+        loop {
+            match (a_elements.next(), b_elements.next()) {
+                (Some(a_el), Some(b_el)) => match self.cmp_tokens(a_el, b_el) {
+                    Ordering::Equal => {},
+                    ord => return ord,
+                },
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (None, None) => return Ordering::Equal,
+            }
+        }
+    }
+
+    // Same ordering as `Ord for Item`
+    pub fn cmp(&self, a: usize, b: usize) -> Ordering {
+        self.cmp_tokens(self.packets[a], self.packets[b])
+    }
+
+    // Number of packets ordered before the given one
+    pub fn count_below(&self, packet: usize) -> usize {
+        (0..self.len()).filter(|other| self.cmp(*other, packet) == Ordering::Less).count()
+    }
+
+    // 1-based position of the packet when all packets are sorted
+    pub fn rank(&self, packet: usize) -> usize {
+        self.count_below(packet) + 1
+    }
+
+    // Sorted packets greater than low and less than high
+    pub fn between(&self, low: usize, high: usize) -> Vec<usize> {
+        let mut packets = (0..self.len())
+            .filter(|packet| self.cmp(low, *packet) == Ordering::Less && self.cmp(*packet, high) == Ordering::Less)
+            .collect::<Vec<usize>>();
+        packets.sort_by(|a, b| self.cmp(*a, *b));
+        packets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_d13_arena() {
+        let texts = ["[1,[2,[3]],4]", "[[]]", "[]", "[1,[2,[3]],4,0]", "[1,2]", "[[1],2]", "[-1]", "[[[]]]"];
+        let packets = texts.iter().map(|text| Packet::from_str(text).expect("Parse failed")).collect::<Vec<Packet>>();
+        let mut arena = PacketArena::new();
+        for packet in packets.iter() {
+            arena.push(packet);
+        }
+        assert_eq!(arena.len(), texts.len());
+        assert_eq!(&arena.tokens[..6], &[
+            Token::List { end: 7 }, Token::Number(1), Token::List { end: 6 }, Token::Number(2), Token::List { end: 6 }, Token::Number(3),
+        ]);
+        for a in 0..texts.len() {
+            for b in 0..texts.len() {
+                assert_eq!(arena.cmp(a, b), packets[a].cmp(&packets[b]), "{} vs {}", texts[a], texts[b]);
+            }
+        }
+        // [] < [[]] < [[[]]] < [-1] < [1,2] = [[1],2] < [1,[2,[3]],4] < [1,[2,[3]],4,0]
+        assert_eq!(arena.count_below(2), 0);
+        assert_eq!(arena.count_below(7), 2);
+        assert_eq!(arena.count_below(5), 4);
+        assert_eq!(arena.count_below(0), 6);
+        assert_eq!(arena.count_below(3), 7);
+        assert_eq!((arena.rank(2), arena.rank(3)), (1, 8));
+        assert_eq!(arena.between(7, 0), vec![6, 4, 5]);
+        assert_eq!(arena.between(4, 5), vec![]);
+        assert_eq!(arena.between(3, 2), vec![]);
+
+        // Parsed packets have the same tokens as the pushed ones
+        let mut parsed = PacketArena::new();
+        for text in texts.iter() {
+            parsed.parse(text).expect("Parse failed");
+        }
+        assert_eq!((&parsed.tokens, &parsed.packets), (&arena.tokens, &arena.packets));
+        // Failed packet leaves nothing behind
+        assert_eq!(parsed.parse("[1,[2,3"), Err(String::from("unexpected end of packet at column 8")));
+        assert_eq!(parsed.parse("[[]]").expect("Parse failed"), texts.len());
+        assert_eq!(parsed.cmp(texts.len(), 1), Ordering::Equal);
+        assert_eq!(parsed.tokens.len(), arena.tokens.len() + 2);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{cmp::Ordering, fmt, io::{Read, BufReader}};
+
+use super::{packets, Item, Packet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+// Single comparison step, path holds list indices leading to the compared elements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Compare { path: Vec<usize>, left: String, right: String },
+    // Number on given side is compared as a list containing just that number
+    Promote { path: Vec<usize>, side: Side, number: i32 },
+    // First differing numbers, the given side has the smaller one
+    Smaller { path: Vec<usize>, side: Side, left: i32, right: i32 },
+    // List on given side has no more items
+    RanOut { path: Vec<usize>, side: Side },
+}
+
+fn format_path(path: &[usize]) -> String {
+    path.iter().fold(String::from("$"), |text, index| format!("{text}[{index}]"))
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decision::Compare { path, left, right } => write!(f, "at {}: compare {left} vs {right}", format_path(path)),
+            Decision::Promote { path, side, number } => write!(f, "at {}: {side} {number} promoted to [{number}]", format_path(path)),
+            Decision::Smaller { path, side, left, right } => write!(f, "at {}: {side} side is smaller ({left} vs {right})", format_path(path)),
+            Decision::RanOut { path, side } => write!(f, "at {}: {side} side ran out of items", format_path(path)),
+        }
+    }
+}
+
+// Outcome of the comparison with all the steps leading to it
+pub struct Explanation {
+    pub ordering: Ordering,
+    pub decisions: Vec<Decision>,
+}
+
+fn explain(left: &Item, right: &Item, path: &mut Vec<usize>, decisions: &mut Vec<Decision>) -> Ordering {
+    decisions.push(Decision::Compare { path: path.clone(), left: left.to_string(), right: right.to_string() });
+    match (left, right) {
+        (Item::Number { item: a }, Item::Number { item: b }) => {
+            let ordering = a.cmp(b);
+            match ordering {
+                Ordering::Less => decisions.push(Decision::Smaller { path: path.clone(), side: Side::Left, left: *a, right: *b }),
+                Ordering::Greater => decisions.push(Decision::Smaller { path: path.clone(), side: Side::Right, left: *a, right: *b }),
+                Ordering::Equal => {},
+            }
+            ordering
+        },
+        (Item::Array { items: a }, Item::Array { items: b }) => {
+            for index in 0..a.len().max(b.len()) {
+                let (a_el, b_el) = match (a.get(index), b.get(index)) {
+                    (Some(a_el), Some(b_el)) => (a_el, b_el),
+                    (None, _) => {
+                        decisions.push(Decision::RanOut { path: path.clone(), side: Side::Left });
+                        return Ordering::Less;
+                    },
+                    (_, None) => {
+                        decisions.push(Decision::RanOut { path: path.clone(), side: Side::Right });
+                        return Ordering::Greater;
+                    },
+                };
+                path.push(index);
+                let ordering = explain(a_el, b_el, path, decisions);
+                path.pop();
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        },
+        (Item::Number { item: a }, b) => {
+            decisions.push(Decision::Promote { path: path.clone(), side: Side::Left, number: *a });
+            explain(&Item::Array { items: vec![Item::Number { item: *a }] }, b, path, decisions)
+        },
+        (a, Item::Number { item: b }) => {
+            decisions.push(Decision::Promote { path: path.clone(), side: Side::Right, number: *b });
+            explain(a, &Item::Array { items: vec![Item::Number { item: *b }] }, path, decisions)
+        },
+    }
+}
+
+// Same ordering as `Ord for Item`, but with the trace of how it was decided
+pub fn compare_explained(left: &Packet, right: &Packet) -> Explanation {
+    let mut decisions = Vec::new();
+    let ordering = explain(left, right, &mut Vec::new(), &mut decisions);
+    Explanation { ordering, decisions }
+}
+
+pub fn explain_pairs<R: Read>(input: BufReader<R>) -> Result<String, String> {
+    let packets = packets(input)?;
+    if packets.len() % 2 != 0 {
+        return Err(String::from("Packets must come in pairs"));
+    }
+    let mut output = String::new();
+    for (index, pair) in packets.chunks(2).enumerate() {
+        let explanation = compare_explained(&pair[0], &pair[1]);
+        output.push_str(&format!("== Pair {} ==\n", index + 1));
+        for decision in explanation.decisions {
+            output.push_str(&format!("{decision}\n"));
+        }
+        output.push_str(match explanation.ordering {
+            Ordering::Less => "Right order\n\n",
+            Ordering::Equal => "Packets are equal\n\n",
+            Ordering::Greater => "Wrong order\n\n",
+        });
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::test_util::str_to_buf_reader;
+
+    #[test]
+    fn test_d13_explain() {
+        let packet = |text: &str| Packet::from_str(text).expect("Parse failed");
+        let explanation = compare_explained(&packet("[[1],[2,3,4]]"), &packet("[[1],4]"));
+        assert_eq!(explanation.ordering, Ordering::Less);
+        assert_eq!(explanation.decisions.iter().map(|d| d.to_string()).collect::<Vec<String>>(), vec![
+            "at $: compare [[1],[2,3,4]] vs [[1],4]",
+            "at $[0]: compare [1] vs [1]",
+            "at $[0][0]: compare 1 vs 1",
+            "at $[1]: compare [2,3,4] vs 4",
+            "at $[1]: right 4 promoted to [4]",
+            "at $[1]: compare [2,3,4] vs [4]",
+            "at $[1][0]: compare 2 vs 4",
+            "at $[1][0]: left side is smaller (2 vs 4)",
+        ]);
+        assert_eq!(explanation.decisions.last(), Some(&Decision::Smaller { path: vec![1, 0], side: Side::Left, left: 2, right: 4 }));
+
+        let explanation = compare_explained(&packet("[7,7,7,7]"), &packet("[7,7,7]"));
+        assert_eq!(explanation.ordering, Ordering::Greater);
+        assert_eq!(explanation.decisions.last(), Some(&Decision::RanOut { path: vec![], side: Side::Right }));
+
+        let explanation = compare_explained(&packet("[[]]"), &packet("[[]]"));
+        assert_eq!(explanation.ordering, Ordering::Equal);
+
+        let output = explain_pairs(str_to_buf_reader(&String::from("[1,1,3,1,1]\n[1,1,5,1,1]\n\n[[[]]]\n[[]]\n"))).expect("Explain failed");
+        assert_eq!(output, "== Pair 1 ==
+at $: compare [1,1,3,1,1] vs [1,1,5,1,1]
+at $[0]: compare 1 vs 1
+at $[1]: compare 1 vs 1
+at $[2]: compare 3 vs 5
+at $[2]: left side is smaller (3 vs 5)
+Right order
+
+== Pair 2 ==
+at $: compare [[[]]] vs [[]]
+at $[0]: compare [[]] vs []
+at $[0]: right side ran out of items
+Wrong order
+
+");
+
+        // Explained ordering always matches the one used for sorting
+        let f = std::fs::File::open("src/d13/input.txt").expect("No src/13/input.txt file");
+        let packets = packets(BufReader::new(f)).expect("Parse failed");
+        for left in packets.iter().take(40) {
+            for right in packets.iter().take(40) {
+                assert_eq!(compare_explained(left, right).ordering, left.cmp(right), "{left} vs {right}");
+            }
+        }
+    }
+}
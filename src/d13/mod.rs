@@ -1,4 +1,10 @@
-use std::{io::{Read, BufReader, Bytes}, cmp::Ordering};
+use std::{io::{Read, BufReader, BufRead}, cmp::Ordering, fmt, str::FromStr};
+
+mod arena;
+mod explain;
+
+use arena::PacketArena;
+pub use explain::explain_pairs;
 
 #[derive(Debug)]
 enum Item {
@@ -6,46 +12,32 @@ enum Item {
     Number { item: i32 },
 }
 
+// Number compared as if it was a list containing just that number, without allocating the list
+fn cmp_number_list(number: i32, items: &[Item]) -> Ordering {
+    match items.first() {
+        None => Ordering::Greater,
+        Some(Item::Number { item }) => number.cmp(item).then(1.cmp(&items.len())),
+        Some(Item::Array { items: nested }) => cmp_number_list(number, nested).then(1.cmp(&items.len())),
+    }
+}
+
+fn cmp_lists(a: &[Item], b: &[Item]) -> Ordering {
+    for (a_el, b_el) in a.iter().zip(b.iter()) {
+        match a_el.cmp(b_el) {
+            Ordering::Equal => {},
+            ord => return ord,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
 impl Ord for Item {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Item::Number { item: a }, Item::Number { item: b }) => {
-                return a.cmp(b);
-            },
-            (Item::Array { items: a }, Item::Array { items: b }) => {
-                let mut index = 0;
-                loop {
-                    let a_el = match a.get(index) {
-                        Some(a) => a,
-                        None => {
-                            return match b.get(index) {
-                                Some(_) => Ordering::Less,
-                                None => Ordering::Equal,
-                            };
-                        }
-                    };
-                    match b.get(index) {
-                        Some(b_el) => match a_el.cmp(b_el) {
-                            Ordering::Equal => {},
-                            ord => {
-                                return ord;
-                            },
-                        },
-                        None => {
-                            return Ordering::Greater;
-                        }
-                    }
-                    index += 1;
-                }
-            },
-            (Item::Number { item: a }, b @ Item::Array { items: _ }) => {
-                let arr = Item::Array { items: vec![Item::Number { item: *a }] };
-                return arr.cmp(b);
-            },
-            (a @ Item::Array { items: _ }, Item::Number { item: b }) => {
-                let arr = Item::Array { items: vec![Item::Number { item: *b }] };
-                return a.cmp(&arr);
-            }
+            (Item::Number { item: a }, Item::Number { item: b }) => a.cmp(b),
+            (Item::Array { items: a }, Item::Array { items: b }) => cmp_lists(a, b),
+            (Item::Number { item: a }, Item::Array { items: b }) => cmp_number_list(*a, b),
+            (Item::Array { items: a }, Item::Number { item: b }) => cmp_number_list(*b, a).reverse(),
         }
     }
 }
@@ -65,131 +57,294 @@ impl PartialEq for Item {
 impl Eq for Item {
 }
 
-fn parse_into(str: &String, into: &mut Vec<Item>) -> Result<(), String> {
-    if str.len() == 0 {
-        return Ok(());
-    }
-    match str.parse::<i32>() {
-        Ok(num) => {
-            into.push(Item::Number { item: num });
-            Ok(())
+// Same syntax as in the input, e.g. [1,[2,3],[]]
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Number { item } => write!(f, "{item}"),
+            Item::Array { items } => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            },
         }
-        Err(e) => Err(format!("Unable to parse {}: {}", str, e.to_string())),
     }
 }
 
-fn process<R: Read>(iter: &mut Bytes<BufReader<R>>, into: &mut Vec<Item>) -> Result<(), String> {
-    let mut current = String::new();
-
-    loop {
-        match iter.next() {
-            Some(Ok(ch)) => {
-                match ch {
-                    b'\n' => {
-                        assert!(current.len() == 0);
-                        return parse_into(&current, into);
-                    },
-                    b']' => {
-                        return parse_into(&current, into);
-                    },
-                    b'[' => {
-                        let mut v: Vec<Item> = Vec::new();
-                        process(iter, &mut v)?;
-                        into.push(Item::Array { items: v });
-                    },
-                    b',' => {
-                        parse_into(&current, into)?;
-                        current.clear();
-                    },
-                    x => {
-                        current.push(x as char);
+impl Item {
+    // Every list element on its own line, nested lists are indented by two spaces
+    fn pretty(&self) -> String {
+        let mut output = String::new();
+        self.pretty_into(&mut output, 0);
+        output.push('\n');
+        output
+    }
+
+    fn pretty_into(&self, output: &mut String, indent: usize) {
+        match self {
+            Item::Array { items } if !items.is_empty() => {
+                output.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    output.push_str(&" ".repeat(indent + 2));
+                    item.pretty_into(output, indent + 2);
+                    if index + 1 < items.len() {
+                        output.push(',');
                     }
+                    output.push('\n');
                 }
+                output.push_str(&" ".repeat(indent));
+                output.push(']');
             },
-            Some(Err(e)) => return Err(format!("Error: {}", e.to_string())),
-            None => {
-                return parse_into(&current, into);
-            },
+            item => output.push_str(&item.to_string()),
         }
     }
 }
 
-pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
-    let mut iter = input.bytes();
-    let mut count = 0usize;
-    let mut index = 1usize;
-    let mut done = false;
-
-    let marker1 = Item::Array { items: vec![
-        Item::Array { items: vec![
-            Item::Number { item: 2 },
-        ]},
-    ]};
-    let marker2 = Item::Array { items: vec![
-        Item::Array { items: vec![
-            Item::Number { item: 6 },
-        ]},
-    ]};
-
-    let mut all: Vec<Item> = vec![
-        Item::Array { items: vec![
-            Item::Array { items: vec![
-                Item::Number { item: 2 },
-            ]},
-        ]},
-        Item::Array { items: vec![
-            Item::Array { items: vec![
-                Item::Number { item: 6 },
-            ]},
-        ]},
-    ];
-
-    while !done {
-        let mut first: Vec<Item> = Vec::new();
-        process(&mut iter, &mut first)?;
-
-        let mut second: Vec<Item> = Vec::new();
-        process(&mut iter, &mut second)?;
-
-        match iter.next() {
-            Some(Ok(b'\n')) => {},
-            Some(Ok(ch)) => return Err(format!("Expected new line, '{}' found", ch as char)),
-            Some(Err(e)) => return Err(format!("Input error: {}", e.to_string())),
-            None => {
-                done = true;
-            },
+// Whole line of the input, always a list
+type Packet = Item;
+
+// Receives the packet structure while it is being parsed, so packets can be stored in any form
+trait PacketSink {
+    fn number(&mut self, number: i32);
+    fn start_list(&mut self);
+    fn end_list(&mut self);
+}
+
+// Builds the `Item` tree, lists being built are on the stack
+struct ItemBuilder {
+    stack: Vec<Vec<Item>>,
+}
+
+impl PacketSink for ItemBuilder {
+    fn number(&mut self, number: i32) {
+        if let Some(items) = self.stack.last_mut() {
+            items.push(Item::Number { item: number });
         }
+    }
 
-        if first.le(&second) {
-            count += index;
+    fn start_list(&mut self) {
+        self.stack.push(Vec::new());
+    }
+
+    fn end_list(&mut self) {
+        let list = match self.stack.pop() {
+            Some(items) => Item::Array { items },
+            None => return,
+        };
+        if let Some(items) = self.stack.last_mut() {
+            items.push(list);
         }
+    }
+}
 
-        all.push(Item::Array { items: first });
-        all.push(Item::Array { items: second });
+struct PacketParser<'a, S: PacketSink> {
+    text: &'a [u8],
+    pos: usize,
+    sink: &'a mut S,
+}
 
-        index += 1;
+impl<S: PacketSink> PacketParser<'_, S> {
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.pos).is_some_and(|ch| ch.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
     }
 
-    all.sort();
+    // Columns are counted from 1
+    fn unexpected(&self) -> String {
+        match self.text.get(self.pos) {
+            Some(ch) => format!("unexpected '{}' at column {}", *ch as char, self.pos + 1),
+            None => format!("unexpected end of packet at column {}", self.pos + 1),
+        }
+    }
 
-    let mut position1 = 0usize;
-    let mut position2 = 0usize;
-    let m1 = &marker1;
-    for (index, item) in all.iter().enumerate() {
-        if item.eq(&m1) {
-            position1 = index + 1;
+    // value := number | list
+    fn value(&mut self) -> Result<(), String> {
+        match self.text.get(self.pos) {
+            Some(b'[') => self.list(),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    // list := '[' (value (',' value)*)? ']'
+    fn list(&mut self) -> Result<(), String> {
+        if self.text.get(self.pos) != Some(&b'[') {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        self.sink.start_list();
+        self.skip_whitespace();
+        if self.text.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            self.sink.end_list();
+            return Ok(());
         }
-        if item.eq(&marker2) {
-            position2 = index + 1;
+        loop {
+            self.skip_whitespace();
+            self.value()?;
+            self.skip_whitespace();
+            match self.text.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    self.sink.end_list();
+                    return Ok(());
+                },
+                _ => return Err(self.unexpected()),
+            }
         }
     }
 
+    // number := '-'? digit+
+    fn number(&mut self) -> Result<(), String> {
+        let start = self.pos;
+        if self.text.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        if !self.text.get(self.pos).is_some_and(|ch| ch.is_ascii_digit()) {
+            return Err(self.unexpected());
+        }
+        while self.text.get(self.pos).is_some_and(|ch| ch.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        // Only ASCII digits and sign were consumed
+        let number = String::from_utf8_lossy(&self.text[start..self.pos]);
+        match number.parse::<i32>() {
+            Ok(item) => {
+                self.sink.number(item);
+                Ok(())
+            },
+            Err(_) => Err(format!("number {number} out of range at column {}", start + 1)),
+        }
+    }
+}
+
+// Whole packet is a single list, the sink may have received part of it when parsing fails
+fn parse_packet<S: PacketSink>(text: &str, sink: &mut S) -> Result<(), String> {
+    let mut parser = PacketParser { text: text.as_bytes(), pos: 0, sink };
+    parser.skip_whitespace();
+    parser.list()?;
+    parser.skip_whitespace();
+    match parser.pos < text.len() {
+        true => Err(parser.unexpected()),
+        false => Ok(()),
+    }
+}
+
+impl FromStr for Packet {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut builder = ItemBuilder { stack: vec![Vec::new()] };
+        parse_packet(text, &mut builder)?;
+        match builder.stack.pop().and_then(|mut root| root.pop()) {
+            Some(packet) => Ok(packet),
+            None => Err(String::from("empty packet")),
+        }
+    }
+}
+
+// Calls on_line for every packet line of the input, empty lines are skipped
+fn read_packets<R: Read, F: FnMut(&str) -> Result<(), String>>(input: BufReader<R>, mut on_line: F) -> Result<(), String> {
+    for (number, line) in input.lines().enumerate() {
+        let line = match line {
+            Ok(ln) => ln,
+            Err(e) => return Err(format!("Unable to read line: {}", e)),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Err(e) = on_line(&line) {
+            return Err(format!("Line {}: {}", number + 1, e));
+        }
+    }
+    Ok(())
+}
+
+fn packets<R: Read>(input: BufReader<R>) -> Result<Vec<Packet>, String> {
+    let mut packets = Vec::new();
+    read_packets(input, |line| {
+        packets.push(Packet::from_str(line)?);
+        Ok(())
+    })?;
+    Ok(packets)
+}
+
+pub fn pretty_print<R: Read>(input: BufReader<R>) -> Result<String, String> {
+    Ok(packets(input)?.iter().map(|packet| packet.pretty()).collect::<Vec<String>>().join("\n"))
+}
+
+const DIVIDERS: [&str; 2] = ["[[2]]", "[[6]]"];
+
+fn parse_dividers(dividers: &[&str]) -> Result<Vec<Packet>, String> {
+    dividers.iter().map(|divider| match Packet::from_str(divider) {
+        Ok(packet) => Ok(packet),
+        Err(e) => Err(format!("Divider {divider}: {e}")),
+    }).collect()
+}
+
+// Packets parsed straight into the flat form, first packet of each pair has even id
+fn read_arena<R: Read>(input: BufReader<R>) -> Result<PacketArena, String> {
+    let mut arena = PacketArena::new();
+    read_packets(input, |line| arena.parse(line).map(|_| ()))?;
+    if !arena.len().is_multiple_of(2) {
+        return Err(String::from("Packets must come in pairs"));
+    }
+    Ok(arena)
+}
+
+// Adds dividers to the packets and returns their 1-based positions in sorted packets
+fn divider_positions(arena: &mut PacketArena, dividers: &[Packet]) -> Vec<usize> {
+    let ids = dividers.iter().map(|divider| arena.push(divider)).collect::<Vec<usize>>();
+    ids.iter().map(|id| arena.rank(*id)).collect()
+}
+
+// Position of every divider and the number of packets between consecutive ones
+pub fn divider_report<R: Read>(input: BufReader<R>, dividers: &[&str]) -> Result<String, String> {
+    let mut arena = read_arena(input)?;
+    let first = arena.len();
+    let packets = parse_dividers(dividers)?;
+    let positions = divider_positions(&mut arena, &packets);
+    let mut report = String::new();
+    for (divider, position) in packets.iter().zip(positions.iter()) {
+        report.push_str(&format!("{divider}: position {position}\n"));
+    }
+    let mut sorted = (first..arena.len()).collect::<Vec<usize>>();
+    sorted.sort_by(|a, b| arena.cmp(*a, *b));
+    for pair in sorted.windows(2) {
+        let between = arena.between(pair[0], pair[1]).len();
+        report.push_str(&format!("{} - {}: {between} packets between\n", packets[pair[0] - first], packets[pair[1] - first]));
+    }
+    Ok(report)
+}
+
+pub fn run_with_dividers<R: Read>(input: BufReader<R>, dividers: &[&str]) -> Result<(String, String), String> {
+    let mut arena = read_arena(input)?;
+
+    let count = (0..arena.len() / 2)
+        .filter(|index| arena.cmp(2 * index, 2 * index + 1) != Ordering::Greater)
+        .map(|index| index + 1)
+        .sum::<usize>();
+
+    // Position of a divider in sorted packets is given by number of packets before it
+    let decoder_key = divider_positions(&mut arena, &parse_dividers(dividers)?).iter().product::<usize>();
+
     Ok((
         format!("{}", count),
-        format!("{}", position1 * position2),
+        format!("{}", decoder_key),
     ))
 }
 
+pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
+    run_with_dividers(input, &DIVIDERS)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -197,12 +352,9 @@ mod test {
 
     #[test]
     fn test_d13_01() {
-        let input = String::from("[1,[2,[3,[4,[5,6,7]]]],8,9]\n");
-        let mut iter = str_to_buf_reader(&input).bytes();
-        let mut result: Vec<Item> = Vec::new();
-        process(&mut iter, &mut result).expect("Run failed");
+        let result = Packet::from_str("[1,[2,[3,[4,[5,6,7]]]],8,9]").expect("Parse failed");
         assert_eq!(
-            result,
+            [result],
             [
                 Item::Array { items: vec![
                     Item::Number { item: 1 },
@@ -286,6 +438,199 @@ mod test {
         );
     }
 
+    // Small xorshift generator, so the test doesn't need any dependencies
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self, max: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % max
+        }
+
+        fn packet(&mut self, depth: u32) -> Item {
+            let len = self.next(5);
+            Item::Array { items: (0..len).map(|_| match depth > 0 && self.next(3) == 0 {
+                true => self.packet(depth - 1),
+                false => Item::Number { item: self.next(20) as i32 - 5 },
+            }).collect() }
+        }
+    }
+
+    #[test]
+    fn test_d13_parse() {
+        let parse = |text: &str| Packet::from_str(text).map(|packet| packet.to_string());
+        assert_eq!(parse("[1,[2,3],[]]"), Ok(String::from("[1,[2,3],[]]")));
+        assert_eq!(parse("  [ -1 , [ 2,3 ] ,[ ] ]\t"), Ok(String::from("[-1,[2,3],[]]")));
+        assert_eq!(parse("[1,[2,3],[]],]"), Err(String::from("unexpected ',' at column 13")));
+        assert_eq!(parse("[1,[2,3],[4]]]]"), Err(String::from("unexpected ']' at column 14")));
+        assert_eq!(parse("[1,[2,3]"), Err(String::from("unexpected end of packet at column 9")));
+        assert_eq!(parse("[1,,2]"), Err(String::from("unexpected ',' at column 4")));
+        assert_eq!(parse("[1,2,]"), Err(String::from("unexpected ']' at column 6")));
+        assert_eq!(parse("[1 2]"), Err(String::from("unexpected '2' at column 4")));
+        assert_eq!(parse("[-]"), Err(String::from("unexpected ']' at column 3")));
+        assert_eq!(parse("[a]"), Err(String::from("unexpected 'a' at column 2")));
+        assert_eq!(parse("5"), Err(String::from("unexpected '5' at column 1")));
+        assert_eq!(parse(""), Err(String::from("unexpected end of packet at column 1")));
+        assert_eq!(parse("[1,99999999999]"), Err(String::from("number 99999999999 out of range at column 4")));
+
+        let err = run(str_to_buf_reader(&String::from("[1]\n[2]\n\n[3]\n[4]]\n"))).unwrap_err();
+        assert_eq!(err, "Line 5: unexpected ']' at column 4");
+        assert!(run(str_to_buf_reader(&String::from("[1]\n[2]\n\n[3]\n"))).is_err());
+        // Negative numbers are ordered as expected
+        assert_eq!(run(str_to_buf_reader(&String::from("[-1]\n[0]\n\n[[-5]]\n[-6]\n"))).unwrap().0, "1");
+    }
+
+    // Previous implementation, wraps the number into a newly allocated list
+    fn cmp_allocating(a: &Item, b: &Item) -> Ordering {
+        match (a, b) {
+            (Item::Number { item: a }, Item::Number { item: b }) => a.cmp(b),
+            (Item::Array { items: a }, Item::Array { items: b }) => {
+                for (a_el, b_el) in a.iter().zip(b.iter()) {
+                    match cmp_allocating(a_el, b_el) {
+                        Ordering::Equal => {},
+                        ord => return ord,
+                    }
+                }
+                a.len().cmp(&b.len())
+            },
+            (Item::Number { item: a }, b) => cmp_allocating(&Item::Array { items: vec![Item::Number { item: *a }] }, b),
+            (a, Item::Number { item: b }) => cmp_allocating(a, &Item::Array { items: vec![Item::Number { item: *b }] }),
+        }
+    }
+
+    #[test]
+    fn test_d13_mixed_compare() {
+        let packet = |text: &str| Packet::from_str(text).expect("Parse failed");
+        let cases = [
+            ("[1]", "[[1]]", Ordering::Equal),
+            ("[1]", "[[[1]]]", Ordering::Equal),
+            ("[1]", "[[]]", Ordering::Greater),
+            ("[1]", "[[[]]]", Ordering::Greater),
+            ("[1]", "[[1,2]]", Ordering::Less),
+            ("[1]", "[[[1],2]]", Ordering::Less),
+            ("[1]", "[[[1,2]]]", Ordering::Less),
+            ("[2]", "[[1,2]]", Ordering::Greater),
+            ("[[1,2]]", "[1]", Ordering::Greater),
+            ("[[]]", "[0]", Ordering::Less),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(packet(a).cmp(&packet(b)), expected, "{a} vs {b}");
+            assert_eq!(cmp_allocating(&packet(a), &packet(b)), expected, "{a} vs {b}");
+        }
+
+        let mut random = Random(0x9E3779B97F4A7C15);
+        let packets = (0..300).map(|_| random.packet(3)).collect::<Vec<Item>>();
+        for a in packets.iter() {
+            for b in packets.iter() {
+                assert_eq!(a.cmp(b), cmp_allocating(a, b), "{a} vs {b}");
+            }
+        }
+    }
+
+    // Run with `cargo test --release bench_d13 -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_d13_sort() {
+        let mut random = Random(0x2545F4914F6CDD1D);
+        let packets = (0..200_000).map(|_| random.packet(5)).collect::<Vec<Item>>();
+        let order = |cmp: fn(&Item, &Item) -> Ordering| {
+            let mut indices = (0..packets.len()).collect::<Vec<usize>>();
+            let start = std::time::Instant::now();
+            indices.sort_by(|a, b| cmp(&packets[*a], &packets[*b]));
+            (indices, start.elapsed())
+        };
+        let (allocating, allocating_time) = order(cmp_allocating);
+        let (current, current_time) = order(Item::cmp);
+        println!("Sorting {} packets: allocating {:?}, current {:?}", packets.len(), allocating_time, current_time);
+        assert_eq!(allocating, current);
+    }
+
+    #[test]
+    fn test_d13_display() {
+        let input = String::from("[1,[2,[3,[4,[5,6,7]]]],8,9]\n[[],[[]],10]\n\n[]\n");
+        let parsed = packets(str_to_buf_reader(&input)).expect("Parse failed");
+        assert_eq!(parsed.iter().map(|p| p.to_string()).collect::<Vec<String>>(), vec![
+            "[1,[2,[3,[4,[5,6,7]]]],8,9]",
+            "[[],[[]],10]",
+            "[]",
+        ]);
+        assert_eq!(parsed[1].pretty(), "[
+  [],
+  [
+    []
+  ],
+  10
+]
+");
+        assert_eq!(pretty_print(str_to_buf_reader(&String::from("[1,[2]]\n[]"))).unwrap(), "[
+  1,
+  [
+    2
+  ]
+]
+
+[]
+");
+        assert!(packets(str_to_buf_reader(&String::from("[1]5"))).is_err());
+        assert_eq!(Packet::from_str(&parsed[0].pretty()).map(|packet| packet.to_string()), Ok(parsed[0].to_string()));
+
+        let mut random = Random(0x2545F4914F6CDD1D);
+        for _ in 0..1000 {
+            let packet = random.packet(4);
+            let text = packet.to_string();
+            let parsed = packets(str_to_buf_reader(&text)).expect("Parse failed");
+            assert_eq!(parsed.len(), 1);
+            // Equal packets can still differ in structure ([1] and 1), so compare the text too
+            assert_eq!(parsed[0], packet);
+            assert_eq!(parsed[0].to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_d13_dividers() {
+        let input = String::from("[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]
+");
+        let mut arena = read_arena(str_to_buf_reader(&input)).expect("Parse failed");
+        assert_eq!(divider_positions(&mut arena, &parse_dividers(&DIVIDERS).unwrap()), vec![10, 14]);
+
+        let result = run_with_dividers(str_to_buf_reader(&input), &["[[6]]", "[0]", "[[2]]", "[10]"]).expect("Run failed");
+        assert_eq!(result.1, format!("{}", 15 * 4 * 11 * 20));
+
+        let report = divider_report(str_to_buf_reader(&input), &["[[6]]", "[[2]]", "[5]"]).expect("Report failed");
+        assert_eq!(report, "[[6]]: position 15
+[[2]]: position 10
+[5]: position 14
+[[2]] - [5]: 3 packets between
+[5] - [[6]]: 0 packets between
+");
+        let err = run_with_dividers(str_to_buf_reader(&input), &["[[2]", "[[6]]"]).unwrap_err();
+        assert_eq!(err, "Divider [[2]: unexpected end of packet at column 5");
+    }
+
     #[test]
     fn test_d13_final() {
         let f = std::fs::File::open("src/d13/input.txt").expect("No src/13/input.txt file");
@@ -0,0 +1,135 @@
+use std::cmp;
+
+use super::{Content, Map, Position};
+
+// Dense copy of the map, covering every cell sand can reach
+pub struct Grid {
+    cells: Vec<Option<Content>>,
+    min_x: isize,
+    min_y: isize,
+    width: usize,
+    height: usize,
+    floor: Option<isize>,
+    walls: Option<(isize, isize)>,
+}
+
+impl Grid {
+    pub fn new(map: &Map, sources: &[Position]) -> Self {
+        let min_y = sources.iter().map(|source| source.y).fold(map.min_y, cmp::min);
+        let sources_min_x = sources.iter().map(|source| source.x).fold(map.min_x, cmp::min);
+        let sources_max_x = sources.iter().map(|source| source.x).fold(map.max_x, cmp::max);
+        let (mut min_x, mut max_x, max_y) = match map.floor {
+            // Sand forms a triangle under the sources, so it never spreads further than the floor is deep
+            Some(floor) => {
+                let depth = floor - min_y;
+                (sources_min_x - depth, sources_max_x + depth, floor - 1)
+            },
+            // Sand next to the outermost rocks falls down
+            None => (sources_min_x - 1, sources_max_x + 1, map.max_y),
+        };
+        if let Some((left, right)) = map.walls {
+            min_x = cmp::max(min_x, left + 1);
+            max_x = cmp::min(max_x, right - 1);
+        }
+        let width = (max_x - min_x + 1).max(0) as usize;
+        let height = (max_y - min_y + 1).max(0) as usize;
+        let mut grid = Self {
+            cells: vec![None; width * height],
+            min_x,
+            min_y,
+            width,
+            height,
+            floor: map.floor,
+            walls: map.walls,
+        };
+        for (pos, content) in map.map.iter() {
+            if let Some(index) = grid.index(pos.x, pos.y) {
+                grid.cells[index] = Some(content.clone());
+            }
+        }
+        grid
+    }
+
+    fn index(&self, x: isize, y: isize) -> Option<usize> {
+        let (column, row) = (x - self.min_x, y - self.min_y);
+        match column >= 0 && row >= 0 && (column as usize) < self.width && (row as usize) < self.height {
+            true => Some(row as usize * self.width + column as usize),
+            false => None,
+        }
+    }
+
+    fn is_empty(&self, x: isize, y: isize) -> bool {
+        if self.floor.is_some_and(|floor| y >= floor) {
+            return false;
+        }
+        if self.walls.is_some_and(|(left, right)| x <= left || x >= right) {
+            return false;
+        }
+        match self.index(x, y) {
+            Some(index) => self.cells[index].is_none(),
+            None => true,
+        }
+    }
+
+    // Every grain follows the path of the previous one from the same source until its last open
+    // position, so the paths are kept on stacks and the next grain continues from the top.
+    // Sources take turns until a grain falls forever or all of them are covered.
+    pub fn simulate_sand(&mut self, sources: &[Position]) -> usize {
+        let mut sand_count = 0;
+        let mut paths = sources.iter()
+            .map(|source| match self.is_empty(source.x, source.y) {
+                true => vec![*source],
+                false => Vec::new(),
+            })
+            .collect::<Vec<Vec<Position>>>();
+        while paths.iter().any(|path| !path.is_empty()) {
+            for path in paths.iter_mut() {
+                // Grains from other sources may have settled on the path
+                if sources.len() > 1 {
+                    if let Some(blocked) = path.iter().position(|pos| !self.is_empty(pos.x, pos.y)) {
+                        path.truncate(blocked);
+                    }
+                }
+                while let Some(pos) = path.last() {
+                    let index = match self.index(pos.x, pos.y) {
+                        Some(index) => index,
+                        // Left the grid, so it falls forever
+                        None => return sand_count,
+                    };
+                    let next = [0, -1, 1].into_iter()
+                        .map(|dx| Position { x: pos.x + dx, y: pos.y + 1 })
+                        .find(|next| self.is_empty(next.x, next.y));
+                    match next {
+                        Some(next) => path.push(next),
+                        None => {
+                            self.cells[index] = Some(Content::Sand);
+                            sand_count += 1;
+                            path.pop();
+                            break;
+                        },
+                    }
+                }
+            }
+        }
+        sand_count
+    }
+
+    // With a floor every cell reachable from a source by the falling moves ends up with sand, so
+    // each row is just the cells below sand in the previous row (or sources) which aren't rock
+    pub fn flood_fill(&self, sources: &[Position]) -> Option<usize> {
+        self.floor?;
+        let mut row = vec![false; self.width];
+        let mut sand_count = 0;
+        for y in self.min_y..self.min_y + self.height as isize {
+            let next = (0..self.width).map(|column| {
+                let x = self.min_x + column as isize;
+                let above = y > self.min_y && row[column.saturating_sub(1)..=cmp::min(column + 1, self.width - 1)].iter().any(|sand| *sand);
+                let source = sources.iter().any(|source| source.x == x && source.y == y);
+                (above || source) && self.is_empty(x, y)
+            }).collect::<Vec<bool>>();
+            sand_count += next.iter().filter(|sand| **sand).count();
+            row = next;
+        }
+        Some(sand_count)
+    }
+}
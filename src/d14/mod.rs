@@ -1,4 +1,8 @@
-use std::{io::{Read, BufReader, Bytes}, collections::{HashMap}, cmp, hash::{Hash}};
+use std::{io::{Read, BufReader, BufRead}, collections::{HashMap}, cmp, fmt, hash::{Hash}};
+
+mod grid;
+
+use grid::Grid;
 
 #[derive(Debug, Clone)]
 enum Content {
@@ -6,19 +10,105 @@ enum Content {
     Sand,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 struct Position {
     x: isize,
     y: isize,
 }
 
+#[derive(Clone)]
 struct Map {
     map: HashMap<Position, Content>,
     min_x: isize,
     min_y: isize,
     max_x: isize,
     max_y: isize,
-    with_floor: bool,
+    // Row of the floor, sand falls forever without it
+    floor: Option<isize>,
+    // Columns of the left and right wall
+    walls: Option<(isize, isize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaveConfig {
+    // Sand sources, each emits one grain in turn
+    sources: Vec<Position>,
+    // Floor this many rows below the lowest rock, None for no floor
+    floor_offset: Option<isize>,
+    // Columns of the left and right wall, sand can't get into them or past them
+    walls: Option<(isize, isize)>,
+}
+
+impl Default for CaveConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec![Position { x: 500, y: 0 }],
+            floor_offset: None,
+            walls: None,
+        }
+    }
+}
+
+impl CaveConfig {
+    // Config from command line values: sources as `x,y` (500,0 when none given), floor offset as
+    // number or `none` (2 when not given) and walls as `left,right`
+    pub fn parse(sources: &[&str], floor_offset: Option<&str>, walls: Option<&str>) -> Result<Self, String> {
+        let mut config = Self { floor_offset: Some(2), ..Self::default() };
+        if !sources.is_empty() {
+            config.sources = match sources.iter().map(|source| parse_point(source)).collect() {
+                Ok(sources) => sources,
+                Err(e) => return Err(format!("Invalid source: {}", e)),
+            };
+        }
+        config.floor_offset = match floor_offset {
+            None => Some(2),
+            Some("none") => None,
+            Some(offset) => match offset.parse::<isize>() {
+                Ok(offset) if offset >= 0 => Some(offset),
+                _ => return Err(format!("Invalid floor offset '{}', expected non-negative number or 'none'", offset)),
+            },
+        };
+        if let Some(walls) = walls {
+            config.walls = match parse_point(walls) {
+                Ok(Position { x: left, y: right }) if left < right => Some((left, right)),
+                _ => return Err(format!("Invalid walls '{}', expected left,right", walls)),
+            };
+        }
+        Ok(config)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+fn parse_point(text: &str) -> Result<Position, String> {
+    let text = text.trim();
+    let (x, y) = match text.split_once(',') {
+        Some((x, y)) => (x.trim(), y.trim()),
+        None => return Err(format!("Expected x,y but found '{}'", text)),
+    };
+    let number = |n: &str| match n.parse::<isize>() {
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("Wrong number format '{}': {}", n, e)),
+    };
+    Ok(Position { x: number(x)?, y: number(y)? })
+}
+
+fn parse_points(text: &str) -> Result<Vec<Position>, String> {
+    text.split("->").map(parse_point).collect()
+}
+
+// All cells of the segment including both ends
+fn segment(from: &Position, to: &Position) -> Result<Vec<Position>, String> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+        return Err(format!("Segment {} -> {} is not horizontal, vertical or diagonal", from, to));
+    }
+    let steps = cmp::max(dx.abs(), dy.abs());
+    Ok((0..=steps).map(|step| Position { x: from.x + dx.signum() * step, y: from.y + dy.signum() * step }).collect())
 }
 
 impl Map {
@@ -31,67 +121,94 @@ impl Map {
         self.max_y = cmp::max(self.max_y, y);
     }
 
-    fn parse<R: Read>(input: &mut Bytes<BufReader<R>>) -> Result<Map, String> {
-        let mut current = String::with_capacity(4);
-
-        let mut positions: Vec<isize> = Vec::with_capacity(4);
-
+    // Cave description with one shape per line:
+    //   `498,4 -> 498,6 -> 496,6` path of rock segments, each horizontal, vertical or diagonal
+    //   `fill 10,0 -> 14,4 -> 6,4` polygon filled with rock, the last point connects back to the first
+    // Empty lines and lines starting with `#` are skipped
+    fn parse<R: Read>(input: BufReader<R>) -> Result<Map, String> {
         let mut map = Map {
             map: HashMap::new(),
             min_x: isize::MAX,
             min_y: isize::MAX,
             max_x: 0isize,
             max_y: 0isize,
-            with_floor: false,
+            floor: None,
+            walls: None,
         };
 
-        for maybe_byte in input {
-            match maybe_byte {
-                Ok(b'-') => {},
-                Ok(b'>') => {},
-                Ok(b',') | Ok(b' ') | Ok(b'\n') => {
-                    if !current.is_empty() {
-                        positions.push(
-                            match current.parse::<isize>() {
-                                Ok(n) => n,
-                                Err(e) => return Err(format!("Wrong number format '{}': {}", current, e)),
-                            }
-                        );
-
-                        if positions.len() == 4 {
-                            if positions[0] != positions[2] {
-                                assert_eq!(positions[1], positions[3]);
-                                for i in cmp::min(positions[0], positions[2])..=cmp::max(positions[0], positions[2]) {
-                                    map.insert(i, positions[1], Content::Rock);
-                                }
-                            }
-
-                            if positions[1] != positions[3] {
-                                assert_eq!(positions[0], positions[2]);
-                                for i in cmp::min(positions[1], positions[3])..=cmp::max(positions[1], positions[3]) {
-                                    map.insert(positions[0], i, Content::Rock);
-                                }
-                            }
-
-                            if maybe_byte.unwrap() == b'\n' {
-                                positions.clear();
-                            } else {
-                                positions.remove(0);
-                                positions.remove(0);
-                            }
-                        }
-                    }
-                    current.clear();
-                },
-                Ok(ch) => {
-                    current.push(ch as char);
-                },
+        for (number, line) in input.lines().enumerate() {
+            let line = match line {
+                Ok(ln) => ln,
                 Err(e) => return Err(format!("Unable to read: {}", e)),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match line.strip_prefix("fill ") {
+                Some(points) => map.add_polygon(points),
+                None => map.add_path(line),
+            };
+            if let Err(e) = result {
+                return Err(format!("Line {}: {}", number + 1, e));
             }
         }
         Ok(map)
     }
 
+    fn add_path(&mut self, text: &str) -> Result<(), String> {
+        let points = parse_points(text)?;
+        if let [point] = points.as_slice() {
+            self.insert(point.x, point.y, Content::Rock);
+        }
+        for pair in points.windows(2) {
+            for pos in segment(&pair[0], &pair[1])? {
+                self.insert(pos.x, pos.y, Content::Rock);
+            }
+        }
+        Ok(())
+    }
+
+    fn add_polygon(&mut self, text: &str) -> Result<(), String> {
+        let points = parse_points(text)?;
+        if points.len() < 3 {
+            return Err(format!("Polygon needs at least 3 points, got {}", points.len()));
+        }
+        let edges = points.iter().zip(points.iter().cycle().skip(1)).collect::<Vec<(&Position, &Position)>>();
+        for (from, to) in edges.iter() {
+            for pos in segment(from, to)? {
+                self.insert(pos.x, pos.y, Content::Rock);
+            }
+        }
+
+        // Cells inside cross the edges odd number of times when going right, edges are straight or
+        // diagonal, so the crossings are always at whole columns
+        let min_x = points.iter().map(|pos| pos.x).min().unwrap_or(0);
+        let max_x = points.iter().map(|pos| pos.x).max().unwrap_or(0);
+        let min_y = points.iter().map(|pos| pos.y).min().unwrap_or(0);
+        let max_y = points.iter().map(|pos| pos.y).max().unwrap_or(0);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let crossings = edges.iter()
+                    .filter(|(a, b)| (a.y > y) != (b.y > y) && x < a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y))
+                    .count();
+                if !crossings.is_multiple_of(2) {
+                    self.insert(x, y, Content::Rock);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Same rocks with floor and walls from the config
+    fn variant(&self, config: &CaveConfig) -> Map {
+        Map {
+            floor: config.floor_offset.map(|offset| self.max_y + offset),
+            walls: config.walls,
+            ..self.clone()
+        }
+    }
+
     fn _print(&self) {
         for y in self.min_y..=self.max_y {
             for x in self.min_x..=self.max_x {
@@ -104,15 +221,22 @@ impl Map {
             println!("");
         }
     }
+}
 
+// Simulation directly on the HashMap, kept as reference for the dense grid
+#[cfg(test)]
+impl Map {
     fn is_empty(&self, pos: &Position) -> bool {
         match self.map.get(pos) {
             Some(Content::Rock) | Some(Content::Sand) => false,
             None => {
-                if self.with_floor && pos.y >= self.max_y {
+                if self.floor.is_some_and(|floor| pos.y >= floor) {
                      // Hits the floor
                     return false;
                 }
+                if self.walls.is_some_and(|(left, right)| pos.x <= left || pos.x >= right) {
+                    return false;
+                }
                 // Empty space
                 return true;
             }
@@ -121,7 +245,7 @@ impl Map {
 
     fn drop_sand(&self, start: &Position) -> Option<Position> {
         let mut pos = Position { x: start.x, y: start.y };
-        while pos.y <= self.max_y + 1 {
+        while pos.y <= self.floor.unwrap_or(self.max_y) + 1 {
             // Try down
             if self.is_empty(&Position { x: pos.x, y: pos.y + 1 }) {
                 pos.y += 1;
@@ -163,33 +287,40 @@ impl Map {
     }
 }
 
-pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
-    let start = Position { x: 500, y: 0};
-    let mut map = Map::parse(&mut input.bytes())?;
-    // map._print();
+// Number of grains settled before one falls forever or before all sources are covered
+fn settled_sand(map: &Map, config: &CaveConfig) -> usize {
+    let map = map.variant(config);
+    Grid::new(&map, &config.sources).simulate_sand(&config.sources)
+}
 
-    let mut map2 = Map {
-        map: map.map.clone(),
-        min_x: map.min_x,
-        max_x: map.max_x,
-        min_y: map.min_y,
-        max_y: map.max_y + 2,
-        with_floor: true,
-    };
+// Number of grains when all sources are covered, computed without simulating the grains
+fn filled_sand(map: &Map, config: &CaveConfig) -> Option<usize> {
+    let map = map.variant(config);
+    Grid::new(&map, &config.sources).flood_fill(&config.sources)
+}
 
-    let sand_count1 = map.simulate_sand(&start);
+// Part one is the sand settled before it starts falling forever, so it ignores the floor, part two
+// is the sand when all sources are covered, which only happens with the floor
+pub fn run_with_config<R: Read>(input: BufReader<R>, config: &CaveConfig) -> Result<(String, String), String> {
+    let map = Map::parse(input)?;
     // map._print();
-    // println!("\n+++++++++++++++++++++++++++++++\n");
 
-    let sand_count2 = map2.simulate_sand(&start);
-    // map2._print();
+    let sand_count1 = settled_sand(&map, &CaveConfig { floor_offset: None, ..config.clone() });
+    let sand_count2 = match filled_sand(&map, config) {
+        Some(count) => format!("{}", count),
+        None => String::from("no floor"),
+    };
 
     Ok((
         format!("{}", sand_count1),
-        format!("{}", sand_count2),
+        sand_count2,
     ))
 }
 
+pub fn run<R: Read>(input: BufReader<R>) -> Result<(String, String), String> {
+    run_with_config(input, &CaveConfig { floor_offset: Some(2), ..CaveConfig::default() })
+}
+
 #[cfg(test)]
 mod test {
     use std::io::BufReader;
@@ -213,6 +344,237 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_d14_format() {
+        let parse = |text: &str| Map::parse(str_to_buf_reader(&String::from(text)));
+        let rocks = |map: &Map| {
+            let mut rocks = map.map.keys().map(|pos| (pos.x, pos.y)).collect::<Vec<(isize, isize)>>();
+            rocks.sort();
+            rocks
+        };
+        let map = parse("0,0 -> 3,3 -> 6,0\n").expect("Parse failed");
+        assert_eq!(rocks(&map), vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 2), (5, 1), (6, 0)]);
+        let map = parse("# single rock\n\n  7,1  ").expect("Parse failed");
+        assert_eq!(rocks(&map), vec![(7, 1)]);
+
+        // Diamond, triangle and rectangle
+        assert_eq!(parse("fill 5,0 -> 7,2 -> 5,4 -> 3,2").expect("Parse failed").map.len(), 13);
+        assert_eq!(parse("fill 0,0 -> 4,0 -> 0,4").expect("Parse failed").map.len(), 15);
+        assert_eq!(parse("fill 0,0 -> 3,0 -> 3,2 -> 0,2").expect("Parse failed").map.len(), 12);
+        // Concave polygon keeps its notch empty
+        let map = parse("fill 0,0 -> 4,0 -> 4,4 -> 2,2 -> 0,4").expect("Parse failed");
+        assert_eq!(map.map.len(), 21);
+        assert!(!map.map.contains_key(&Position { x: 2, y: 3 }));
+        assert!(map.map.contains_key(&Position { x: 3, y: 3 }));
+
+        assert_eq!(parse("498,4 -> 500,7").err(), Some(String::from("Line 1: Segment 498,4 -> 500,7 is not horizontal, vertical or diagonal")));
+        assert_eq!(parse("\n# cave\n498,x -> 498,6").err(), Some(String::from("Line 3: Wrong number format 'x': invalid digit found in string")));
+        assert_eq!(parse("498 -> 498,6").err(), Some(String::from("Line 1: Expected x,y but found '498'")));
+        assert_eq!(parse("498,4 ->").err(), Some(String::from("Line 1: Expected x,y but found ''")));
+        assert_eq!(parse("fill 0,0 -> 2,2").err(), Some(String::from("Line 1: Polygon needs at least 3 points, got 2")));
+        assert_eq!(parse("fill 0,0 -> 4,0 -> 4,1").err(), Some(String::from("Line 1: Segment 4,1 -> 0,0 is not horizontal, vertical or diagonal")));
+
+        // Sand slides along diagonal rocks the same way in all simulations
+        let map = parse("# funnel above a block
+490,5 -> 497,12
+510,5 -> 503,12
+fill 495,16 -> 505,16 -> 500,21
+").expect("Parse failed");
+        let start = Position { x: 500, y: 0 };
+        let simulated = simulate(&map, &[start]);
+        assert_eq!(simulated as i32, map.clone().simulate_sand(&start));
+        let floor = CaveConfig { floor_offset: Some(2), ..CaveConfig::default() };
+        let (simulated, filled) = counts_with_config(&map, &floor);
+        assert_eq!(Some(simulated), filled);
+        assert_eq!(simulated as i32, map.variant(&floor).simulate_sand(&start));
+    }
+
+    fn load() -> Map {
+        let f = std::fs::File::open("src/d14/input.txt").expect("No src/14/input.txt file");
+        Map::parse(BufReader::new(f)).expect("Parse failed")
+    }
+
+    fn with_floor(map: &Map) -> Map {
+        map.variant(&CaveConfig { floor_offset: Some(2), ..CaveConfig::default() })
+    }
+
+    fn simulate(map: &Map, sources: &[Position]) -> usize {
+        Grid::new(map, sources).simulate_sand(sources)
+    }
+
+    fn flood_fill(map: &Map, sources: &[Position]) -> Option<usize> {
+        Grid::new(map, sources).flood_fill(sources)
+    }
+
+    // Both ways of counting the sand, they must agree whenever there is a floor
+    fn counts_with_config(map: &Map, config: &CaveConfig) -> (usize, Option<usize>) {
+        (settled_sand(map, config), filled_sand(map, config))
+    }
+
+    #[test]
+    fn test_d14_grid() {
+        let input = String::from("498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+");
+        let start = Position { x: 500, y: 0 };
+        let map = Map::parse(str_to_buf_reader(&input)).expect("Parse failed");
+        assert_eq!(simulate(&map, &[start]), 24);
+        assert_eq!(simulate(&with_floor(&map), &[start]), 93);
+
+        // Source at other places, including outside of the rocks and in the rock itself
+        let map = load();
+        for start in [Position { x: 480, y: 3 }, Position { x: 300, y: 0 }, Position { x: 520, y: 20 }] {
+            for mut variant in [with_floor(&map), map.clone()] {
+                let dense = simulate(&variant, &[start]);
+                assert_eq!(dense as i32, variant.simulate_sand(&start), "{start:?}");
+            }
+        }
+        let rock = *map.map.keys().next().expect("No rocks");
+        assert_eq!(simulate(&map, &[rock]), 0);
+    }
+
+    #[test]
+    fn test_d14_flood_fill() {
+        let input = String::from("498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+");
+        let start = Position { x: 500, y: 0 };
+        let mut map = with_floor(&Map::parse(str_to_buf_reader(&input)).expect("Parse failed"));
+        assert_eq!(flood_fill(&map, &[start]), Some(93));
+        assert_eq!(map.simulate_sand(&start), 93);
+
+        let map = load();
+        assert_eq!(flood_fill(&map, &[start]), None);
+        let rock = *map.map.keys().next().expect("No rocks");
+        assert_eq!(flood_fill(&with_floor(&map), &[rock]), Some(0));
+        for start in [Position { x: 500, y: 0 }, Position { x: 480, y: 3 }, Position { x: 300, y: 0 }, Position { x: 520, y: 20 }, Position { x: 500, y: 160 }] {
+            let floor = with_floor(&map);
+            let filled = flood_fill(&floor, &[start]);
+            assert_eq!(filled, Some(simulate(&floor, &[start])), "{start:?}");
+        }
+        // Source right above the floor
+        let mut shallow = with_floor(&map);
+        let start = Position { x: 500, y: shallow.floor.expect("No floor") - 1 };
+        assert_eq!(flood_fill(&shallow, &[start]), Some(1));
+        assert_eq!(shallow.simulate_sand(&start), 1);
+    }
+
+    // Grain by grain reference for multiple sources
+    fn round_robin(map: &mut Map, sources: &[Position]) -> usize {
+        let mut sand_count = 0;
+        while sources.iter().any(|source| map.is_empty(source)) {
+            for source in sources.iter() {
+                if !map.is_empty(source) {
+                    continue;
+                }
+                match map.drop_sand(source) {
+                    Some(pos) => map.insert(pos.x, pos.y, Content::Sand),
+                    None => return sand_count,
+                }
+                sand_count += 1;
+            }
+        }
+        sand_count
+    }
+
+    #[test]
+    fn test_d14_config() {
+        let input = String::from("498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+");
+        let map = Map::parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let source = Position { x: 500, y: 0 };
+        assert_eq!(counts_with_config(&map, &CaveConfig::default()), (24, None));
+        let floor = CaveConfig { floor_offset: Some(2), ..CaveConfig::default() };
+        assert_eq!(counts_with_config(&map, &floor), (93, Some(93)));
+        // Variant doesn't change the original map
+        assert_eq!((map.floor, map.variant(&floor).floor), (None, Some(11)));
+        assert_eq!(map.variant(&floor).map.len(), map.map.len());
+
+        // Floor right under the lowest rock gives a triangle of 10 rows minus the rocks and shadows
+        let config = CaveConfig { floor_offset: Some(1), ..CaveConfig::default() };
+        let (simulated, filled) = counts_with_config(&map, &config);
+        assert_eq!(Some(simulated), filled);
+        assert!(simulated < 93);
+
+        // Walls close to the source make a narrow shaft
+        let walls = CaveConfig { walls: Some((498, 502)), ..floor.clone() };
+        let (simulated, filled) = counts_with_config(&map, &walls);
+        assert_eq!(Some(simulated), filled);
+        let mut hash_map = map.variant(&walls);
+        assert_eq!(simulated as i32, hash_map.simulate_sand(&source));
+        // Without floor the walls keep sand from falling past the sides, so it piles up against them
+        let walls = CaveConfig { walls: Some((493, 504)), ..CaveConfig::default() };
+        let (simulated, filled) = counts_with_config(&map, &walls);
+        assert_eq!(filled, None);
+        assert_eq!(simulated as i32, map.variant(&walls).simulate_sand(&source));
+        assert!(simulated > 24);
+
+        // Sources take turns, the first one falling forever stops the simulation
+        let sources = vec![source, Position { x: 497, y: 0 }];
+        let config = CaveConfig { sources: sources.clone(), ..CaveConfig::default() };
+        let (simulated, filled) = counts_with_config(&map, &config);
+        assert_eq!((simulated, filled), (5, None));
+        assert_eq!(simulated, round_robin(&mut map.variant(&config), &sources));
+
+        let big = load();
+        let sources = vec![source, Position { x: 470, y: 5 }, Position { x: 530, y: 2 }, Position { x: 500, y: 30 }];
+        for config in [
+            CaveConfig { sources: sources.clone(), floor_offset: Some(2), walls: None },
+            CaveConfig { sources: sources.clone(), floor_offset: Some(5), walls: Some((450, 540)) },
+            CaveConfig { sources: sources.clone(), floor_offset: Some(0), walls: Some((495, 505)) },
+        ] {
+            let (simulated, filled) = counts_with_config(&big, &config);
+            assert_eq!(Some(simulated), filled, "{config:?}");
+            assert_eq!(simulated, round_robin(&mut big.variant(&config), &config.sources), "{config:?}");
+        }
+        assert_eq!(counts_with_config(&big, &CaveConfig { sources: Vec::new(), ..floor.clone() }), (0, Some(0)));
+    }
+
+    // Run with `cargo test --release bench_d14 -- --ignored --nocapture`
+    #[test]
+    fn test_d14_run_with_config() {
+        let input = String::from("498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+");
+        let run = |config: &CaveConfig| run_with_config(str_to_buf_reader(&input), config);
+        let default = CaveConfig::parse(&[], None, None).expect("Parse failed");
+        assert_eq!(run(&default), Ok((String::from("24"), String::from("93"))));
+        assert_eq!(run(&default), run(&CaveConfig::parse(&["500,0"], Some("2"), None).unwrap()));
+        assert_eq!(run(&CaveConfig::parse(&[], Some("none"), None).unwrap()), Ok((String::from("24"), String::from("no floor"))));
+
+        let config = CaveConfig::parse(&["500,0", "497,0"], Some("5"), Some("490,510")).expect("Parse failed");
+        assert_eq!(config.sources, vec![Position { x: 500, y: 0 }, Position { x: 497, y: 0 }]);
+        assert_eq!((config.floor_offset, config.walls), (Some(5), Some((490, 510))));
+        let map = Map::parse(str_to_buf_reader(&input)).expect("Parse failed");
+        let (_, filled) = counts_with_config(&map, &config);
+        assert_eq!(run(&config), Ok((String::from("5"), format!("{}", filled.unwrap()))));
+
+        assert!(CaveConfig::parse(&["500"], None, None).is_err());
+        assert!(CaveConfig::parse(&[], Some("-1"), None).is_err());
+        assert!(CaveConfig::parse(&[], Some("low"), None).is_err());
+        assert!(CaveConfig::parse(&[], None, Some("510,490")).is_err());
+        assert!(CaveConfig::parse(&[], None, Some("490")).is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_d14_sand() {
+        let start = Position { x: 500, y: 0 };
+        let map = load();
+
+        let time = std::time::Instant::now();
+        let hash_map = with_floor(&map).simulate_sand(&start);
+        let hash_map_time = time.elapsed();
+
+        let time = std::time::Instant::now();
+        let dense = simulate(&with_floor(&map), &[start]);
+        let dense_time = time.elapsed();
+
+        println!("Part two: HashMap {:?}, dense grid {:?}", hash_map_time, dense_time);
+        assert_eq!(hash_map, dense as i32);
+    }
+
     #[test]
     fn test_d14_final() {
         let f = std::fs::File::open("src/d14/input.txt").expect("No src/14/input.txt file");
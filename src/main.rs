@@ -1,4 +1,4 @@
-use std::{env, path::Path, fs::File, io::BufReader};
+use std::{env, path::Path, fs::File, io::{self, BufReader}};
 mod d01;
 mod d02;
 mod d03;
@@ -17,7 +17,92 @@ mod d15;
 mod d16;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let mode = match args.get(1).map(|arg| arg.as_str()) {
+        Some("debug" | "asm" | "disasm" | "trace" | "csv" | "sweep" | "map" | "distances" | "pretty" | "dividers") => Some(args.remove(1)),
+        _ => None,
+    };
+    let rounds = match args.iter().position(|arg| arg == "--rounds") {
+        Some(pos) if pos + 1 < args.len() => {
+            args.remove(pos);
+            match args.remove(pos).parse::<u64>() {
+                Ok(rounds) => Some(rounds),
+                Err(e) => panic!("Invalid --rounds value: {}", e),
+            }
+        },
+        Some(_) => panic!("Missing --rounds value"),
+        None => None,
+    };
+    let cost = match args.iter().position(|arg| arg == "--cost") {
+        Some(pos) if pos + 1 < args.len() => {
+            args.remove(pos);
+            match d12::cost_model(&args.remove(pos)) {
+                Ok(cost) => Some(cost),
+                Err(e) => panic!("Invalid --cost value: {}", e),
+            }
+        },
+        Some(_) => panic!("Missing --cost value"),
+        None => None,
+    };
+    let source = match args.iter().position(|arg| arg == "--source") {
+        Some(pos) if pos + 1 < args.len() => {
+            args.remove(pos);
+            match args.remove(pos).parse::<char>() {
+                Ok(source) => Some(source),
+                Err(e) => panic!("Invalid --source value: {}", e),
+            }
+        },
+        Some(_) => panic!("Missing --source value"),
+        None => None,
+    };
+    let explain = match args.iter().position(|arg| arg == "--explain") {
+        Some(pos) => {
+            args.remove(pos);
+            true
+        },
+        None => false,
+    };
+    let mut dividers: Vec<String> = Vec::new();
+    while let Some(pos) = args.iter().position(|arg| arg == "--divider") {
+        if pos + 1 >= args.len() {
+            panic!("Missing --divider value");
+        }
+        args.remove(pos);
+        dividers.push(args.remove(pos));
+    }
+    let dividers: Vec<&str> = dividers.iter().map(|divider| divider.as_str()).collect();
+    let mut sources: Vec<String> = Vec::new();
+    while let Some(pos) = args.iter().position(|arg| arg == "--sand") {
+        if pos + 1 >= args.len() {
+            panic!("Missing --sand value");
+        }
+        args.remove(pos);
+        sources.push(args.remove(pos));
+    }
+    let sources: Vec<&str> = sources.iter().map(|source| source.as_str()).collect();
+    let floor = match args.iter().position(|arg| arg == "--floor") {
+        Some(pos) if pos + 1 < args.len() => {
+            args.remove(pos);
+            Some(args.remove(pos))
+        },
+        Some(_) => panic!("Missing --floor value"),
+        None => None,
+    };
+    let walls = match args.iter().position(|arg| arg == "--walls") {
+        Some(pos) if pos + 1 < args.len() => {
+            args.remove(pos);
+            Some(args.remove(pos))
+        },
+        Some(_) => panic!("Missing --walls value"),
+        None => None,
+    };
+    let cave = match (sources.is_empty(), &floor, &walls) {
+        (true, None, None) => None,
+        _ => match d14::CaveConfig::parse(&sources, floor.as_deref(), walls.as_deref()) {
+            Ok(config) => Some(config),
+            Err(e) => panic!("Invalid cave config: {}", e),
+        },
+    };
     let day: String;
     let input: &Path;
     let filename: String;
@@ -33,16 +118,90 @@ fn main() {
             input = Path::new(&args[2]);
         },
         _ => {
-            panic!("Usage: {} day", args[0]);
+            panic!("Usage: {} [debug|asm|disasm|trace|csv|sweep|map|distances|pretty|dividers] day [input] [--rounds N] [--cost MODEL] [--source S|E|a] [--explain] [--divider PACKET]... [--sand X,Y]... [--floor N|none] [--walls L,R]", args[0]);
         }
     };
 
+    // Every flag is only used by some modes of some days, mode None is the plain run
+    let flags = [
+        ("--rounds", rounds.is_some()),
+        ("--cost", cost.is_some()),
+        ("--source", source.is_some()),
+        ("--explain", explain),
+        ("--divider", !dividers.is_empty()),
+        ("--sand", !sources.is_empty()),
+        ("--floor", floor.is_some()),
+        ("--walls", walls.is_some()),
+    ];
+    for (flag, given) in flags {
+        let supported = match (flag, mode.as_deref(), day.as_str()) {
+            ("--rounds", None | Some("trace" | "csv" | "sweep"), "11") => true,
+            ("--cost", Some("map" | "csv"), "12") => true,
+            ("--source", Some("distances"), "12") => true,
+            ("--explain", None, "13") => true,
+            ("--divider", None | Some("dividers"), "13") => !explain,
+            ("--sand" | "--floor" | "--walls", None, "14") => true,
+            _ => false,
+        };
+        if given && !supported {
+            match &mode {
+                Some(mode) => panic!("{} is not supported for {} of day: {}", flag, mode, day),
+                None => panic!("{} is not supported for day: {}", flag, day),
+            }
+        }
+    }
+
+    if day == "11" {
+        let warnings = match File::open(input) {
+            Ok(f) => d11::check(BufReader::new(f)),
+            Err(e) => panic!("Unable to open file {}: {}", input.display(), e),
+        };
+        match warnings {
+            Ok(warnings) => warnings.iter().for_each(|warning| eprintln!("Warning: {}", warning)),
+            Err(e) => panic!("Failed: {}", e),
+        }
+    }
+
     let file = match File::open(input) {
         Ok(f) => f,
         Err(e) => panic!("Unable to open file {}: {}", input.display(), e.to_string()),
     };
     let reader = BufReader::new(file);
 
+    if let Some(mode) = mode {
+        let result = match (mode.as_str(), day.as_str()) {
+            ("debug", "10") => d10::debug(reader, io::stdin().lock(), io::stdout()),
+            ("asm", "10") => d10::assemble(reader).map(|program| print!("{}", program)),
+            ("disasm", "10") => d10::disassemble(reader).map(|program| print!("{}", program)),
+            ("trace", "11") => d11::report_text(reader, rounds.unwrap_or(20) as usize, 3).map(|report| print!("{}", report)),
+            ("csv", "11") => d11::report_csv(reader, rounds.unwrap_or(20) as usize, 3).map(|report| print!("{}", report)),
+            ("sweep", "11") => d11::run_sweep(reader, rounds.unwrap_or(20) as usize).map(|report| print!("{}", report)),
+            ("map", "12") => d12::path_map(reader, cost.as_deref().unwrap_or(&d12::Unit)).map(|map| print!("{}", map)),
+            ("csv", "12") => d12::path_csv(reader, cost.as_deref().unwrap_or(&d12::Unit)).map(|csv| print!("{}", csv)),
+            ("distances", "12") => d12::distance_report(reader, source.unwrap_or('E')).map(|report| print!("{}", report)),
+            ("pretty", "13") => d13::pretty_print(reader).map(|packets| print!("{}", packets)),
+            ("dividers", "13") => match dividers.is_empty() {
+                true => Err(String::from("No --divider given")),
+                false => d13::divider_report(reader, &dividers).map(|report| print!("{}", report)),
+            },
+            _ => {
+                panic!("No {} available for day: {}", mode, day);
+            }
+        };
+        if let Err(e) = result {
+            panic!("Failed: {}", e);
+        }
+        return;
+    }
+
+    if explain {
+        match d13::explain_pairs(reader) {
+            Ok(explanation) => print!("{}", explanation),
+            Err(e) => panic!("Failed: {}", e),
+        }
+        return;
+    }
+
     let result = match day.as_str() {
         "01" => d01::run(reader),
         "02" => d02::run(reader),
@@ -54,10 +213,19 @@ fn main() {
         "08" => d08::run(reader),
         "09" => d09::run(reader),
         "10" => d10::run(reader),
-        "11" => d11::run(reader),
+        "11" => match rounds {
+            Some(rounds) => d11::run_with_rounds(reader, rounds),
+            None => d11::run(reader),
+        },
         "12" => d12::run(reader),
-        "13" => d13::run(reader),
-        "14" => d14::run(reader),
+        "13" => match dividers.is_empty() {
+            true => d13::run(reader),
+            false => d13::run_with_dividers(reader, &dividers),
+        },
+        "14" => match &cave {
+            Some(config) => d14::run_with_config(reader, config),
+            None => d14::run(reader),
+        },
         "15" => d15::run(reader),
         "16" => d16::run(reader),
         _ => {